@@ -3,18 +3,20 @@
 // SPDX-License-Identifier: GPL-2.0-or-later
 
 use chrono::NaiveDate;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use macaddr::MacAddr6;
-use revpi_hat_eep::RevPiHatEeprom;
-use rpi_hat_eep::{gpio_map, Eep, EepAtom, EepAtomCustomData, ToBytes};
+use revpi_hat_eep::gpio::{
+    GpioBankBackPower, GpioBankDrive, GpioBankHysteresis, GpioBankSlew, GpioFsel, GpioPull,
+};
+use revpi_hat_eep::{ConfigFormat, RevPiCustomField, RevPiError, RevPiHatEeprom};
+use rpi_hat_eep::{gpio_map, Eep, EepAtomData, EepAtomType, LinuxDTB, ToBytes};
 use std::fs::OpenOptions;
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::process;
-
-// Disable manual_strip Clippy warning.
-// In parse_prefixed_int() it is not possible to do it the suggested way.
-#[allow(clippy::manual_strip)]
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 
 /// Convert a string slice to an integer, the base is determind from the prefix.
 ///
@@ -25,6 +27,9 @@ use std::process;
 /// ```
 /// assert_eq!(parse_prefixed_int("0xA"), Ok(10));
 /// ```
+// Disable manual_strip Clippy warning.
+// In parse_prefixed_int() it is not possible to do it the suggested way.
+#[allow(clippy::manual_strip)]
 fn parse_prefixed_int<T>(src: &str) -> Result<T, String>
 where
     T: num::Unsigned + num::Num<FromStrRadixErr = std::num::ParseIntError>,
@@ -68,251 +73,2575 @@ fn test_parse_prefixed_int() {
     );
 }
 
-fn calc_uuid(pid: u16, pver: u16, prev: u16, serial: u32) -> uuid::Uuid {
-    let mut bytes: Vec<u8> = Vec::with_capacity(10);
-    bytes.extend_from_slice(&u16::to_le_bytes(pid));
-    bytes.extend_from_slice(&u16::to_le_bytes(pver));
-    bytes.extend_from_slice(&u16::to_le_bytes(prev));
-    bytes.extend_from_slice(&u32::to_le_bytes(serial));
-    let digest = md5::compute(&bytes);
-    uuid::Builder::from_md5_bytes(*digest).into_uuid()
+/// Convert a string slice to a date.
+///
+/// Besides a date in the format YYYY-MM-DD (ISO8601/RFC3339) the keywords `today` and
+/// `yesterday` are accepted. They are resolved relative to the current local date.
+fn parse_edate(src: &str) -> Result<NaiveDate, String> {
+    let today = chrono::Local::now().date_naive();
+    match src {
+        "today" => Ok(today),
+        "yesterday" => today
+            .pred_opt()
+            .ok_or_else(|| "can't determine the date of yesterday".to_string()),
+        _ => src.parse::<NaiveDate>().map_err(|e| {
+            format!("{e} (expected YYYY-MM-DD or one of the keywords `today', `yesterday')")
+        }),
+    }
 }
 
-fn create_rpi_eep(config: RevPiHatEeprom) -> Result<rpi_hat_eep::Eep, Box<dyn std::error::Error>> {
-    let serial = config
-        .serial
-        .expect("BUG: Missing serial in RevPiHatEeprom configuration");
-    let edate = config
-        .edate
-        .expect("BUG: Missing end test date in RevPiHatEeprom configuration");
-    let mac = config
-        .mac
-        .expect("BUG: Missing mac address in RevPiHatEeprom confirguration");
-
-    let uuid = calc_uuid(config.pid, config.pver, config.prev, serial);
-    let vendor_data = rpi_hat_eep::EepAtomVendorData::new(
-        uuid,
-        config.pid,
-        config.pver,
-        config.vstr,
-        config.pstr,
-    )?;
-
-    let gpio_bank0_map: gpio_map::EepAtomGpioMapData = config.gpiobanks[0]
-        .clone()
-        .into_gpio_map(gpio_map::GpioBank::Bank0)?;
-    let mut eep = Eep::new(vendor_data, gpio_bank0_map);
-
-    let dtb = rpi_hat_eep::EepAtomLinuxDTBData::new(rpi_hat_eep::LinuxDTB::Name(config.dtstr));
-    eep.push(EepAtom::new_linux_dtb(dtb))?;
+#[test]
+fn test_parse_edate() {
+    let today = chrono::Local::now().date_naive();
+    assert_eq!(
+        parse_edate("2022-09-27"),
+        Ok(NaiveDate::from_ymd_opt(2022, 9, 27).unwrap())
+    );
+    // The date might change between the two calls, so allow both values
+    let parsed = parse_edate("today").unwrap();
+    assert!(parsed == today || parsed == today.succ_opt().unwrap());
+    assert_eq!(
+        parse_edate("yesterday").unwrap(),
+        parsed.pred_opt().unwrap()
+    );
+    assert!(parse_edate("tomorrow")
+        .unwrap_err()
+        .contains("expected YYYY-MM-DD or one of the keywords"));
+    assert!(parse_edate("2022-13-01").is_err());
+}
 
-    // custom_0
-    let data = EepAtomCustomData::new(config.version.to_string().into_bytes());
-    eep.push(EepAtom::new_custom(data))?;
+/// Parse a mac address and reject the ones which can't be the address of a single device
+fn parse_mac(src: &str) -> Result<MacAddr6, String> {
+    let mac = src.parse::<MacAddr6>().map_err(|e| e.to_string())?;
+    revpi_hat_eep::validate_mac(mac, false).map_err(|e| e.to_string())?;
+    Ok(mac)
+}
 
-    // custom_1
-    let data = EepAtomCustomData::new(serial.to_string().into_bytes());
-    eep.push(EepAtom::new_custom(data))?;
+#[test]
+fn test_parse_mac() {
+    assert_eq!(
+        parse_mac("C8-3E-A7-DE-AD-BE"),
+        Ok("c8:3e:a7:de:ad:be".parse().unwrap())
+    );
+    assert_eq!(
+        parse_mac("00:00:00:00:00:00").unwrap_err(),
+        "invalid mac address: all zeros"
+    );
+    assert!(parse_mac("ff:ff:ff:ff:ff:ff").is_err());
+    assert!(parse_mac("01:00:5e:00:00:01").is_err());
+    assert!(parse_mac("c8:3e:a7:de:ad").is_err());
+}
 
-    // custom_2
-    let data = EepAtomCustomData::new(config.prev.to_string().into_bytes());
-    eep.push(EepAtom::new_custom(data))?;
+/// The byte order of the UUID in the vendor info atom (see: [rpi_hat_eep::UuidByteOrder])
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum UuidByteOrder {
+    /// reversed byte order (HAT specification)
+    Reversed,
+    /// RFC 4122 byte order (for interoperability tests only)
+    Normal,
+}
 
-    // custom_3
-    let data = EepAtomCustomData::new(edate.to_string().into_bytes());
-    eep.push(EepAtom::new_custom(data))?;
+impl From<UuidByteOrder> for rpi_hat_eep::UuidByteOrder {
+    fn from(order: UuidByteOrder) -> Self {
+        match order {
+            UuidByteOrder::Reversed => rpi_hat_eep::UuidByteOrder::Reversed,
+            UuidByteOrder::Normal => rpi_hat_eep::UuidByteOrder::Normal,
+        }
+    }
+}
 
-    // custom_4
-    let data = EepAtomCustomData::new("0".as_bytes().to_vec());
-    eep.push(EepAtom::new_custom(data))?;
+/// Create the image of a full config (see [RevPiHatEeprom::to_eep()])
+fn create_rpi_eep(
+    config: &RevPiHatEeprom,
+    uuid_byte_order: UuidByteOrder,
+) -> Result<Eep, RevPiError> {
+    config.to_eep_with_uuid_byte_order(uuid_byte_order.into())
+}
 
-    // custom_5
-    let data = EepAtomCustomData::new(mac.to_string().into_bytes());
-    eep.push(EepAtom::new_custom(data))?;
+/// Return the config field a RevPi custom atom written by [create_rpi_eep()] is created from
+fn custom_atom_source(field: RevPiCustomField) -> &'static str {
+    match field {
+        RevPiCustomField::FormatVersion => "config.version",
+        RevPiCustomField::Serial => "config.serial",
+        RevPiCustomField::ProductRevision => "config.prev",
+        RevPiCustomField::EndtestDate => "config.edate",
+        RevPiCustomField::Lot => "fixed value `0`",
+        RevPiCustomField::FirstMac => "config.mac",
+        RevPiCustomField::EepromDataVersion => "config.eeprom_data_version",
+        RevPiCustomField::MacCount => "config.mac_count",
+    }
+}
 
-    // custom_6
-    let data = EepAtomCustomData::new(config.eeprom_data_version.to_string().into_bytes());
-    eep.push(EepAtom::new_custom(data))?;
+/// Return the number of atoms [create_rpi_eep()] is expected to create for a config
+///
+/// These are the vendor info, the gpio bank 0 map, the DT blob, the RevPi custom atoms and the
+/// optional gpio bank 1 map.
+fn expected_atom_count(config: &RevPiHatEeprom) -> usize {
+    3 + RevPiCustomField::ALL.len()
+        + config.mac_count.is_some() as usize
+        + config.gpiobanks.len().saturating_sub(1)
+}
 
-    if config.gpiobanks.len() > 1 {
-        let gpio_bank1_map: gpio_map::EepAtomGpioMapData = config.gpiobanks[1]
-            .clone()
-            .into_gpio_map(gpio_map::GpioBank::Bank1)?;
-        eep.push(EepAtom::new_gpio_bank1_map(gpio_bank1_map))?;
+/// Annotate each atom of an image created by [create_rpi_eep()] with the config fields it was
+/// created from
+fn explain(eep: &Eep) -> Vec<String> {
+    let mut custom_index = 0;
+    let mut lines = Vec::new();
+    for atom in eep.atoms() {
+        let line = match atom.atype() {
+            EepAtomType::VendorInfo => format!(
+                "atom {}: {}: uuid <- calculated from config.pid, config.pver, config.prev and \
+                config.serial; pid <- config.pid; pver <- config.pver; vstr <- config.vstr; \
+                pstr <- config.pstr",
+                atom.count(),
+                atom.atype()
+            ),
+            EepAtomType::GpioBank0Map => format!(
+                "atom {}: {} <- config.gpiobanks[0]",
+                atom.count(),
+                atom.atype()
+            ),
+            EepAtomType::LinuxDTB => {
+                format!("atom {}: {} <- config.dtstr", atom.count(), atom.atype())
+            }
+            EepAtomType::ManufCustomData => {
+                let line = match RevPiCustomField::from_index(custom_index) {
+                    Some(field) => format!(
+                        "atom {}: custom_{custom_index} ({field}) <- {}",
+                        atom.count(),
+                        custom_atom_source(field)
+                    ),
+                    None => format!("atom {}: custom_{custom_index} <- unknown", atom.count()),
+                };
+                custom_index += 1;
+                line
+            }
+            EepAtomType::GpioBank1Map => format!(
+                "atom {}: {} <- config.gpiobanks[1]",
+                atom.count(),
+                atom.atype()
+            ),
+            EepAtomType::Unknown => format!(
+                "atom {}: {} (0x{:04x}) <- unknown",
+                atom.count(),
+                atom.atype(),
+                atom.raw_atype()
+            ),
+        };
+        lines.push(line);
     }
-    Ok(eep)
+    lines
 }
 
-#[derive(Parser)]
-#[clap(author, version, about, long_about = None)]
-pub struct Cli {
-    /// The serial number for the device. It is mandatory if the serial is not included in the
-    /// config file. This option will override the serial from the config file.
-    #[clap(long, value_parser = parse_prefixed_int::<u32>)]
-    pub serial: Option<u32>,
-    /// The end test date for the device. In the format YYYY-MM-DD (ISO8601/RFC3339). If omitted the
-    /// current date is used. This option will override a given edate attribute from the config file.
-    #[clap(long)]
-    pub edate: Option<NaiveDate>,
-    /// The (first) mac address of the device. It is mandatory if the mac is not included in the
-    /// config file. This option will override the mac from the config file.
-    #[clap(long)]
-    pub mac: Option<MacAddr6>,
-    /// Full json configuration export file name. The full json configuration includes also the
-    /// serial, edate and mac.
-    #[clap(long, value_parser, value_name = "EXPORT_CONFIG")]
-    pub export: Option<PathBuf>,
-    /// Configuration file in JSON format
-    #[clap(value_parser, value_name = "CONFIG")]
-    pub config: PathBuf,
-    /// Output file name
-    #[clap(value_parser, value_name = "OUTPUT", default_value = "out.eep")]
-    pub outfile_name: PathBuf,
+/// The example config with the serial 1, the edate 2024-01-01 and the mac c8:3e:a7:00:00:01
+#[cfg(test)]
+fn test_config() -> RevPiHatEeprom {
+    let mut config =
+        revpi_hat_eep::parse_config(include_str!("../../../docs/example.json")).unwrap();
+    config.serial = Some(1);
+    config.edate = Some(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+    config.mac = Some("c8:3e:a7:00:00:01".parse().unwrap());
+    config
 }
 
-fn export_config(config: &RevPiHatEeprom, export_path: PathBuf) {
-    let json = serde_json::to_string(config)
-        .expect("BUG: Can't create (full) json from RevPiHatEeprom config");
-    let mut export_file = match OpenOptions::new()
-        .read(false)
-        .write(true)
-        .truncate(true)
-        .create(true)
-        .open(&export_path)
-    {
-        Ok(file) => file,
-        Err(e) => {
-            eprintln!(
-                "ERROR: Can't open json export file: `{}': {e}",
-                export_path.to_string_lossy()
-            );
-            process::exit(1);
-        }
-    };
-    if let Err(e) = export_file.write_all(json.as_bytes()) {
-        eprintln!(
-            "ERROR: Can't write to file `{}`: {e}",
-            export_path.to_string_lossy()
-        );
-        process::exit(1);
+#[test]
+fn test_expected_atom_count() {
+    let mut config = test_config();
+    let eep = create_rpi_eep(&config, UuidByteOrder::Reversed).unwrap();
+    assert_eq!(expected_atom_count(&config), 11);
+    assert_eq!(eep.atoms().len(), 11);
+
+    config.gpiobanks.truncate(1);
+    let eep = create_rpi_eep(&config, UuidByteOrder::Reversed).unwrap();
+    assert_eq!(expected_atom_count(&config), 10);
+    assert_eq!(eep.atoms().len(), 10);
+}
+
+#[test]
+fn test_rebuild() {
+    let config = test_config();
+    let mut original = Vec::new();
+    create_rpi_eep(&config, UuidByteOrder::Reversed)
+        .unwrap()
+        .to_bytes(&mut original);
+
+    // An exported config must result in the same image
+    let export = serde_json::to_string(&config).unwrap();
+    let config = revpi_hat_eep::parse_config(&export).unwrap();
+    let mut rebuilt = Vec::new();
+    create_rpi_eep(&config, UuidByteOrder::Reversed)
+        .unwrap()
+        .to_bytes(&mut rebuilt);
+    assert_eq!(original, rebuilt);
+}
+
+#[test]
+fn test_explain() {
+    let config = test_config();
+    let eep = create_rpi_eep(&config, UuidByteOrder::Reversed).unwrap();
+
+    let lines = explain(&eep);
+    assert_eq!(lines.len(), eep.atoms().len());
+    assert!(lines[0].starts_with("atom 0: vendor info: uuid <- calculated"));
+    assert_eq!(lines[1], "atom 1: GPIO (bank 0) map <- config.gpiobanks[0]");
+    assert_eq!(lines[2], "atom 2: Linux device tree blob <- config.dtstr");
+    assert_eq!(lines[6], "atom 6: custom_3 (endtest date) <- config.edate");
+    assert_eq!(
+        lines[9],
+        "atom 9: custom_6 (EEPROM data version) <- config.eeprom_data_version"
+    );
+    assert_eq!(
+        lines[10],
+        "atom 10: GPIO (bank 1) map <- config.gpiobanks[1]"
+    );
+}
+
+/// Return the name of a gpio bank setting as used in the JSON configuration
+fn config_name<T: serde::Serialize>(value: T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::from("?"),
     }
 }
 
-fn main() {
-    let cli = Cli::parse();
+fn hex_string(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02x}")).collect()
+}
 
-    let config = match std::fs::read_to_string(&cli.config) {
-        Ok(config) => config,
-        Err(e) => {
-            eprintln!(
-                "ERROR: Can't read config file `{}': {e}",
-                cli.config.to_string_lossy()
-            );
-            process::exit(1)
-        }
+/// Describe a GPIO map atom with a line per pin
+fn decode_gpio_map(gpio_map: &gpio_map::EepAtomGpioMapData) -> Vec<String> {
+    let offset = match gpio_map.bank() {
+        gpio_map::GpioBank::Bank0 => 0,
+        gpio_map::GpioBank::Bank1 => gpio_map::BANK0_GPIOS,
     };
+    let mut lines = vec![
+        format!(
+            "  drive: {}, slew: {}, hysteresis: {}, back_power: {}",
+            config_name(GpioBankDrive::from(gpio_map.drive())),
+            config_name(GpioBankSlew::from(gpio_map.slew())),
+            config_name(GpioBankHysteresis::from(gpio_map.hysteresis())),
+            config_name(GpioBankBackPower::from(gpio_map.back_power()))
+        ),
+        format!("  {:>4}  {:<6}  {:<7}  used", "gpio", "fsel", "pull"),
+    ];
+    for (n, pin) in gpio_map.gpios().iter().enumerate() {
+        lines.push(format!(
+            "  {:>4}  {:<6}  {:<7}  {}",
+            n + offset,
+            GpioFsel::from(pin.fsel()).to_string(),
+            GpioPull::from(pin.pull()).to_string(),
+            if pin.used() { "yes" } else { "no" }
+        ));
+    }
+    lines
+}
 
-    let mut config = match revpi_hat_eep::parse_config(&config) {
-        Ok(config) => config,
-        Err(e) => {
-            eprintln!(
-                "ERROR: Invalid config file `{}': {e}",
-                cli.config.to_string_lossy(),
-            );
-            process::exit(1);
+/// Describe the atoms of an image in a human readable form (see `--decode`)
+///
+/// The custom atoms are named as written by [create_rpi_eep()].
+fn decode(eep: &Eep) -> Vec<String> {
+    let mut custom_index = 0;
+    let mut lines = Vec::new();
+    for atom in eep.atoms() {
+        lines.push(format!(
+            "atom {}: {} (type: 0x{:04x}, count: {}, dlen: {})",
+            atom.count(),
+            atom.atype(),
+            atom.raw_atype(),
+            atom.count(),
+            atom.data().len() + 2
+        ));
+        lines.extend(decode_atom_data(atom.data(), custom_index));
+        if let EepAtomData::ManufCustomData(_) = atom.data() {
+            custom_index += 1;
         }
-    };
+    }
+    lines
+}
 
-    let serial = if let Some(serial_cli) = cli.serial {
-        if let Some(serial_config) = config.serial {
-            eprintln!(
-                "WARNING: Overriding serial from the config file (`{}`) \
-                with the serial from the program arguments (`{}`).",
-                serial_config,
-                serial_cli
-            );
+/// Describe the data of an atom; `custom_index` is the index of a custom atom
+fn decode_atom_data(data: &EepAtomData, custom_index: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    match data {
+        EepAtomData::VendorInfo(vendor) => {
+            lines.push(format!("  uuid: {}", vendor.uuid()));
+            lines.push(format!("  pid: {}, pver: {}", vendor.pid(), vendor.pver()));
+            lines.push(format!("  vstr: {}", vendor.vstr()));
+            lines.push(format!("  pstr: {}", vendor.pstr()));
         }
-        serial_cli
-    } else if let Some(serial_config) = config.serial {
-        serial_config
-    } else {
-        eprintln!("ERROR: The `serial` was neither specified as argument nor in the config file.");
-        process::exit(1);
-    };
+        EepAtomData::GpioBank0Map(gpio_map) | EepAtomData::GpioBank1Map(gpio_map) => {
+            lines.extend(decode_gpio_map(gpio_map));
+        }
+        EepAtomData::LinuxDTB(dtb) => lines.push(match dtb.data() {
+            LinuxDTB::Name(name) => format!("  name: {name}"),
+            LinuxDTB::Blob(blob) => match std::str::from_utf8(blob) {
+                Ok(name) => format!("  name: {name}"),
+                Err(_) => format!("  blob: {} bytes", blob.len()),
+            },
+        }),
+        EepAtomData::ManufCustomData(data) => {
+            let name = match RevPiCustomField::from_index(custom_index) {
+                Some(field) => format!("custom_{custom_index} ({field})"),
+                None => format!("custom_{custom_index}"),
+            };
+            let value = match std::str::from_utf8(data.data()) {
+                Ok(value) => value.to_string(),
+                Err(_) => hex_string(data.data()),
+            };
+            lines.push(format!("  {name}: {value}"));
+        }
+        EepAtomData::Unknown { raw, .. } => lines.push(format!("  data: {}", hex_string(raw))),
+    }
+    lines
+}
 
-    let edate = if let Some(edate_cli) = cli.edate {
-        if let Some(edate_config) = config.edate {
-            eprintln!(
-                "WARNING: Overriding edate from the config file (`{}`) \
-                with the edate from the program arguments (`{}`).",
-                edate_config,
-                edate_cli
-            )
+/// Describe the data of an atom as JSON; `custom_index` is the index of a custom atom
+fn atom_data_report(data: &EepAtomData, custom_index: usize) -> serde_json::Value {
+    match data {
+        EepAtomData::VendorInfo(vendor) => serde_json::json!({
+            "uuid": vendor.uuid().to_string(),
+            "pid": vendor.pid(),
+            "pver": vendor.pver(),
+            "vstr": vendor.vstr(),
+            "pstr": vendor.pstr(),
+        }),
+        EepAtomData::GpioBank0Map(gpio_map) | EepAtomData::GpioBank1Map(gpio_map) => {
+            let offset = match gpio_map.bank() {
+                gpio_map::GpioBank::Bank0 => 0,
+                gpio_map::GpioBank::Bank1 => gpio_map::BANK0_GPIOS,
+            };
+            let gpios: Vec<serde_json::Value> = gpio_map
+                .gpios()
+                .iter()
+                .enumerate()
+                .map(|(n, pin)| {
+                    serde_json::json!({
+                        "gpio": n + offset,
+                        "fsel": GpioFsel::from(pin.fsel()).to_string(),
+                        "pull": GpioPull::from(pin.pull()).to_string(),
+                        "used": pin.used(),
+                    })
+                })
+                .collect();
+            serde_json::json!({
+                "drive": config_name(GpioBankDrive::from(gpio_map.drive())),
+                "slew": config_name(GpioBankSlew::from(gpio_map.slew())),
+                "hysteresis": config_name(GpioBankHysteresis::from(gpio_map.hysteresis())),
+                "back_power": config_name(GpioBankBackPower::from(gpio_map.back_power())),
+                "gpios": gpios,
+            })
         }
-        edate_cli
-    } else if let Some(edate_config) = config.edate {
-        edate_config
-    } else {
-        chrono::Local::now().date_naive()
-    };
+        EepAtomData::LinuxDTB(dtb) => match dtb.data() {
+            LinuxDTB::Name(name) => serde_json::json!({ "name": name }),
+            LinuxDTB::Blob(blob) => serde_json::json!({ "blob": hex_string(blob) }),
+        },
+        EepAtomData::ManufCustomData(data) => serde_json::json!({
+            "index": custom_index,
+            "field": RevPiCustomField::from_index(custom_index).map(|field| field.name()),
+            "data": hex_string(data.data()),
+            "value": std::str::from_utf8(data.data()).ok(),
+        }),
+        EepAtomData::Unknown { raw, .. } => serde_json::json!({ "data": hex_string(raw) }),
+    }
+}
 
-    let mac = if let Some(mac_cli) = cli.mac {
-        if let Some(mac_config) = config.mac {
-            eprintln!(
-                "WARNING: Overriding mac from the config file (`{}`) \
-                with the mac from the program arguments (`{}`).",
-                mac_config,
-                mac_cli
-            );
+/// Describe an image as JSON (see `--json-report`)
+///
+/// The report contains the header, every atom with its data and the RevPi config reconstructed
+/// from the image (`null` if the image is no RevPi image, the reason is given in `revpi_error`).
+/// Numeric fields are numbers and raw bytes are hex strings.
+fn decode_report(buf: &[u8], eep: &Eep) -> serde_json::Value {
+    let mut custom_index = 0;
+    let mut atoms = Vec::new();
+    for (atom, atom_ref) in eep.atoms().iter().zip(Eep::parse_atoms(buf).flatten()) {
+        atoms.push(serde_json::json!({
+            "type": atom.raw_atype(),
+            "type_name": atom.atype().to_string(),
+            "count": atom.count(),
+            "offset": atom_ref.offset,
+            "dlen": atom.data().len() + 2,
+            "crc16": atom_ref.crc16,
+            "data": atom_data_report(atom.data(), custom_index),
+        }));
+        if let EepAtomData::ManufCustomData(_) = atom.data() {
+            custom_index += 1;
         }
-        mac_cli
-    } else if let Some(mac_config) = config.mac {
-        mac_config
-    } else {
-        eprintln!("ERROR: The `mac` was neither specified as argument nor in the config file.");
-        process::exit(1);
+    }
+    let (revpi, revpi_error) = match RevPiHatEeprom::from_eep(eep) {
+        Ok(config) => (
+            serde_json::to_value(config).expect("BUG: Can't serialize the config"),
+            None,
+        ),
+        Err(e) => (serde_json::Value::Null, Some(e.to_string())),
     };
+    serde_json::json!({
+        "header": {
+            "signature": hex_string(&buf[0..4]),
+            "version": buf[4],
+            "numatoms": u16::from_le_bytes([buf[6], buf[7]]),
+            "eeplen": u32::from_le_bytes([buf[8], buf[9], buf[10], buf[11]]),
+        },
+        "atoms": atoms,
+        "revpi": revpi,
+        "revpi_error": revpi_error,
+    })
+}
 
-    config.serial = Some(serial);
-    config.edate = Some(edate);
-    config.mac = Some(mac);
+/// Compare the atoms of two images (see `--compare-images`)
+///
+/// Every differing atom is reported with a header line and the differing lines of [decode()]
+/// prefixed with `-` for the first and `+` for the second image. So only the differing pins of a
+/// GPIO map are listed. An empty list is returned if the images are equal.
+fn compare_images(a: &Eep, b: &Eep) -> Vec<String> {
+    let mut lines = Vec::new();
+    // The custom atoms are counted per image, the images may differ in the preceding atoms
+    let (mut custom_a, mut custom_b) = (0, 0);
+    for i in 0..a.atoms().len().max(b.atoms().len()) {
+        let (atom_a, atom_b) = (a.atoms().get(i), b.atoms().get(i));
+        let (index_a, index_b) = (custom_a, custom_b);
+        if let Some(EepAtomData::ManufCustomData(_)) = atom_a.map(|atom| atom.data()) {
+            custom_a += 1;
+        }
+        if let Some(EepAtomData::ManufCustomData(_)) = atom_b.map(|atom| atom.data()) {
+            custom_b += 1;
+        }
+        match (atom_a, atom_b) {
+            (Some(atom_a), Some(atom_b)) if atom_a.raw_atype() == atom_b.raw_atype() => {
+                let lines_a = decode_atom_data(atom_a.data(), index_a);
+                let lines_b = decode_atom_data(atom_b.data(), index_b);
+                if atom_a.data() == atom_b.data() {
+                    continue;
+                }
+                lines.push(format!("atom {i}: {}", atom_a.atype()));
+                for j in 0..lines_a.len().max(lines_b.len()) {
+                    let (line_a, line_b) = (lines_a.get(j), lines_b.get(j));
+                    if line_a == line_b {
+                        continue;
+                    }
+                    if let Some(line) = line_a {
+                        lines.push(format!("-{line}"));
+                    }
+                    if let Some(line) = line_b {
+                        lines.push(format!("+{line}"));
+                    }
+                }
+            }
+            (atom_a, atom_b) => {
+                lines.push(format!(
+                    "atom {i}: {} -> {}",
+                    atom_a.map_or(String::from("missing"), |atom| atom.atype().to_string()),
+                    atom_b.map_or(String::from("missing"), |atom| atom.atype().to_string())
+                ));
+            }
+        }
+    }
+    lines
+}
 
-    if let Some(export_path) = cli.export {
-        export_config(&config, export_path)
+/// Limit an image to the eeplen of its header
+///
+/// A device or a dump of it usually has the whole capacity, so the image is followed by padding.
+/// The buffer is returned unchanged if it has no header or the eeplen exceeds it; decoding reports
+/// the error.
+fn trim_to_eeplen(buf: &[u8]) -> &[u8] {
+    let Some(eeplen) = buf.get(8..12) else {
+        return buf;
     };
+    let eeplen = u32::from_le_bytes(eeplen.try_into().unwrap()) as usize;
+    buf.get(..eeplen).unwrap_or(buf)
+}
 
-    let eep = match create_rpi_eep(config) {
-        Ok(eep) => eep,
+#[test]
+fn test_trim_to_eeplen() {
+    let config = test_config();
+    let mut buf: Vec<u8> = Vec::new();
+    create_rpi_eep(&config, UuidByteOrder::Reversed)
+        .unwrap()
+        .to_bytes(&mut buf);
+    let len = buf.len();
+    assert_eq!(trim_to_eeplen(&buf), &buf[..]);
+    buf.resize(len + 100, 0xff);
+    assert_eq!(trim_to_eeplen(&buf).len(), len);
+    assert_eq!(trim_to_eeplen(&buf[..len - 1]).len(), len - 1);
+    assert_eq!(trim_to_eeplen(&buf[..4]).len(), 4);
+}
+
+/// Read and decode an image file and exit with an error if it can't be decoded
+///
+/// The file may be a dump of a whole EEPROM, e.g. read from sysfs, so any bytes after the eeplen
+/// of the header are ignored.
+fn read_image(path: &Path, uuid_byte_order: UuidByteOrder) -> (Vec<u8>, Eep) {
+    let mut buf = match std::fs::read(path) {
+        Ok(buf) => buf,
         Err(e) => {
-            eprintln!("Error: Can't create EEP: {e}");
+            eprintln!(
+                "ERROR: Can't read image file `{}': {e}",
+                path.to_string_lossy()
+            );
             process::exit(1);
         }
     };
-    let mut buf: Vec<u8> = Vec::new();
-    eep.to_bytes(&mut buf);
-
-    let mut output_file = match OpenOptions::new()
-        .read(false)
-        .write(true)
-        .truncate(true)
-        .create(true)
-        .open(&cli.outfile_name)
-    {
-        Ok(file) => file,
+    let len = trim_to_eeplen(&buf).len();
+    buf.truncate(len);
+    let eep = match Eep::from_bytes_with_uuid_byte_order(&buf, uuid_byte_order.into()) {
+        Ok(eep) => eep,
         Err(e) => {
             eprintln!(
-                "ERROR: Can't open output file: `{}': {e}",
-                cli.outfile_name.to_string_lossy()
+                "ERROR: Invalid image file `{}': {e}",
+                path.to_string_lossy()
             );
             process::exit(1);
         }
     };
+    (buf, eep)
+}
 
-    if let Err(e) = output_file.write_all(&buf) {
-        eprintln!(
-            "ERROR: Can't write data to the output file: `{}': {e}",
-            cli.outfile_name.to_string_lossy()
+#[test]
+fn test_read_image_padded() {
+    let config = test_config();
+    let eep = create_rpi_eep(&config, UuidByteOrder::Reversed).unwrap();
+    let mut buf: Vec<u8> = Vec::new();
+    eep.to_bytes(&mut buf);
+    let len = buf.len();
+    // A sysfs dump of a 4 KiB EEPROM
+    buf.resize(4096, 0xff);
+    let path = std::env::temp_dir().join(format!("revpi-eep-test-padded-{}.eep", process::id()));
+    std::fs::write(&path, &buf).unwrap();
+
+    let (read, decoded) = read_image(&path, UuidByteOrder::Reversed);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(read, &buf[..len]);
+    assert!(compare_images(&eep, &decoded).is_empty());
+}
+
+/// Print the atoms of an image file, as JSON report if `json` is set
+fn decode_image(path: &Path, json: bool, uuid_byte_order: UuidByteOrder) {
+    let (buf, eep) = read_image(path, uuid_byte_order);
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&decode_report(&buf, &eep))
+                .expect("BUG: Can't serialize the report")
         );
-        process::exit(1);
+        return;
+    }
+    println!(
+        "{}: {} atoms, {} bytes",
+        path.to_string_lossy(),
+        eep.atoms().len(),
+        buf.len()
+    );
+    for line in decode(&eep) {
+        println!("{line}");
+    }
+}
+
+#[test]
+fn test_decode() {
+    let config = test_config();
+    let mut buf: Vec<u8> = Vec::new();
+    create_rpi_eep(&config, UuidByteOrder::Reversed)
+        .unwrap()
+        .to_bytes(&mut buf);
+
+    let lines = decode(&Eep::from_bytes(&buf).unwrap());
+    assert!(lines[0].starts_with("atom 0: vendor info (type: 0x0001, count: 0, dlen: "));
+    assert!(lines.contains(&format!("  pid: {}, pver: {}", config.pid, config.pver)));
+    assert!(lines.contains(&format!("  name: {}", config.dtstr)));
+    assert!(lines.contains(&"  custom_1 (serial): 1".to_string()));
+    assert!(lines.contains(&"  custom_3 (endtest date): 2024-01-01".to_string()));
+    // One line per pin of both gpio banks
+    let pins = lines
+        .iter()
+        .filter(|l| l.ends_with("yes") || l.ends_with("no"));
+    assert_eq!(pins.count(), gpio_map::BANK0_GPIOS + gpio_map::BANK1_GPIOS);
+}
+
+#[test]
+fn test_decode_report() {
+    let config = test_config();
+    let mut buf: Vec<u8> = Vec::new();
+    create_rpi_eep(&config, UuidByteOrder::Reversed)
+        .unwrap()
+        .to_bytes(&mut buf);
+    let eep = Eep::from_bytes(&buf).unwrap();
+
+    let report = decode_report(&buf, &eep);
+    assert_eq!(report["header"]["signature"], "522d5069");
+    assert_eq!(report["header"]["version"], 1);
+    assert_eq!(report["header"]["numatoms"], eep.atoms().len());
+    assert_eq!(report["header"]["eeplen"], buf.len());
+    let atoms = report["atoms"].as_array().unwrap();
+    assert_eq!(atoms.len(), eep.atoms().len());
+    assert_eq!(atoms[0]["type"], 1);
+    assert_eq!(atoms[0]["offset"], 12);
+    assert_eq!(atoms[0]["data"]["pid"], config.pid);
+    assert_eq!(atoms[1]["data"]["gpios"][0]["gpio"], 0);
+    assert_eq!(atoms[2]["data"]["name"], config.dtstr);
+    assert_eq!(atoms[4]["data"]["field"], "serial");
+    assert_eq!(atoms[4]["data"]["data"], "31");
+    assert_eq!(atoms[4]["data"]["value"], "1");
+    assert_eq!(report["revpi"]["serial"], 1);
+    assert_eq!(report["revpi"]["edate"], "2024-01-01");
+    assert!(report["revpi_error"].is_null());
+
+    // A plain HAT image has no RevPi config
+    let plain = Eep::new(
+        match eep.atoms()[0].data() {
+            EepAtomData::VendorInfo(vendor) => vendor.clone(),
+            _ => unreachable!(),
+        },
+        match eep.atoms()[1].data() {
+            EepAtomData::GpioBank0Map(gpio_map) => gpio_map.clone(),
+            _ => unreachable!(),
+        },
+    );
+    let mut buf: Vec<u8> = Vec::new();
+    plain.to_bytes(&mut buf);
+    let report = decode_report(&buf, &plain);
+    assert!(report["revpi"].is_null());
+    assert!(report["revpi_error"].is_string());
+}
+
+/// Print the differences of two image files and return `true` if they are equal
+fn compare_image_files(path_a: &Path, path_b: &Path, uuid_byte_order: UuidByteOrder) -> bool {
+    let (buf_a, eep_a) = read_image(path_a, uuid_byte_order);
+    let (buf_b, eep_b) = read_image(path_b, uuid_byte_order);
+    let diff = compare_images(&eep_a, &eep_b);
+    for line in &diff {
+        println!("{line}");
+    }
+    if diff.is_empty() && buf_a == buf_b {
+        println!(
+            "PASS: `{}' and `{}' are equal",
+            path_a.to_string_lossy(),
+            path_b.to_string_lossy()
+        );
+        true
+    } else {
+        println!(
+            "FAIL: `{}' and `{}' differ",
+            path_a.to_string_lossy(),
+            path_b.to_string_lossy()
+        );
+        false
     }
 }
+
+#[test]
+fn test_compare_images() {
+    let config = test_config();
+    let eep_a = create_rpi_eep(&config, UuidByteOrder::Reversed).unwrap();
+    assert!(compare_images(&eep_a, &eep_a).is_empty());
+
+    let mut other = config.clone();
+    other.serial = Some(2);
+    other.gpiobanks = vec![serde_json::from_str(
+        r#"{ "drive": "8mA", "gpios": [ { "gpio": 2, "fsel": "output" } ] }"#,
+    )
+    .unwrap()];
+    let eep_b = create_rpi_eep(&other, UuidByteOrder::Reversed).unwrap();
+    let diff = compare_images(&eep_a, &eep_b);
+    // The uuid and the serial differ
+    assert_eq!(diff[0], "atom 0: vendor info");
+    assert!(diff[1].starts_with("-  uuid: "));
+    assert!(diff[2].starts_with("+  uuid: "));
+    assert!(diff.contains(&"-  custom_1 (serial): 1".to_string()));
+    assert!(diff.contains(&"+  custom_1 (serial): 2".to_string()));
+    // Only the differing pins of the gpio map are listed
+    assert!(diff.contains(&"atom 1: GPIO (bank 0) map".to_string()));
+    assert!(diff.contains(&"+     2  output  default  yes".to_string()));
+    assert!(!diff.iter().any(|line| line.contains("    27  ")));
+    // The gpio bank1 is missing in the second image
+    assert_eq!(
+        diff.last().unwrap(),
+        "atom 10: GPIO (bank 1) map -> missing"
+    );
+
+    // The custom atoms following a differing atom keep their index in each image
+    let mut eep_c = eep_b.clone();
+    eep_c.atoms_mut()[3] = rpi_hat_eep::EepAtom::new_unknown(0x1234, vec![]).unwrap();
+    eep_c.refresh();
+    let diff = compare_images(&eep_a, &eep_c);
+    assert!(diff.contains(&"atom 3: manufacturer custom data -> unknown".to_string()));
+    assert!(diff.contains(&"-  custom_1 (serial): 1".to_string()));
+    assert!(diff.contains(&"+  custom_0 (format version): 2".to_string()));
+}
+
+#[test]
+fn test_create_rpi_eep() {
+    let config = test_config();
+
+    let eep = create_rpi_eep(&config, UuidByteOrder::Reversed).unwrap();
+    let mut buf: Vec<u8> = Vec::new();
+    eep.to_bytes(&mut buf);
+    assert_eq!(buf.len(), 278);
+    // The image must stay byte identical, e.g. a changed UUID would change the device identity
+    assert_eq!(
+        format!("{:x}", md5::compute(&buf)),
+        "3fbb6b22ffd5364eb549e2e16e491905"
+    );
+}
+
+#[test]
+fn test_from_eep() {
+    let config = test_config();
+
+    let mut buf: Vec<u8> = Vec::new();
+    create_rpi_eep(&config, UuidByteOrder::Reversed)
+        .unwrap()
+        .to_bytes(&mut buf);
+    let decoded = RevPiHatEeprom::from_eep(&Eep::from_bytes(&buf).unwrap()).unwrap();
+    assert_eq!(decoded.serial, config.serial);
+    assert_eq!(decoded.edate, config.edate);
+    assert_eq!(decoded.mac, config.mac);
+    assert_eq!(decoded.dtstr, config.dtstr);
+    assert_eq!(decoded.gpiobanks.len(), config.gpiobanks.len());
+
+    // The decoded config creates the same image
+    let mut decoded_buf: Vec<u8> = Vec::new();
+    create_rpi_eep(&decoded, UuidByteOrder::Reversed)
+        .unwrap()
+        .to_bytes(&mut decoded_buf);
+    assert_eq!(buf, decoded_buf);
+}
+
+#[derive(Parser)]
+#[clap(author, version, about, long_about = None)]
+pub struct Cli {
+    /// The serial number for the device. It is mandatory if the serial is neither given by the
+    /// environment variable `REVPI_SERIAL` nor included in the config file. This option will
+    /// override the serial from the environment and the config file.
+    #[clap(long, value_parser = parse_prefixed_int::<u32>)]
+    pub serial: Option<u32>,
+    /// The end test date for the device. In the format YYYY-MM-DD (ISO8601/RFC3339) or one of the
+    /// keywords `today` and `yesterday`. If omitted the environment variable `REVPI_EDATE`, the
+    /// edate attribute from the config file or the current date is used (in this order).
+    #[clap(long, value_parser = parse_edate)]
+    pub edate: Option<NaiveDate>,
+    /// The (first) mac address of the device. It is mandatory if the mac is neither given by the
+    /// environment variable `REVPI_MAC` nor included in the config file. This option will override
+    /// the mac from the environment and the config file.
+    #[clap(long, value_parser = parse_mac)]
+    pub mac: Option<MacAddr6>,
+    /// The number of consecutive mac addresses of the device starting with the mac address. This
+    /// option will override the `mac_count` of the config file. In batch mode the mac addresses of
+    /// the images are incremented by this number.
+    #[clap(long, value_parser = clap::value_parser!(u8).range(1..))]
+    pub mac_count: Option<u8>,
+    /// Override the product id (`pid`) of the config file
+    #[clap(long, value_parser = parse_prefixed_int::<u16>)]
+    pub pid: Option<u16>,
+    /// Override the product version (`pver`) of the config file
+    #[clap(long, value_parser = parse_prefixed_int::<u16>)]
+    pub pver: Option<u16>,
+    /// Override the product revision (`prev`) of the config file
+    #[clap(long, value_parser = parse_prefixed_int::<u16>)]
+    pub prev: Option<u16>,
+    /// Override the vendor string (`vstr`) of the config file
+    #[clap(long)]
+    pub vstr: Option<String>,
+    /// Override the product string (`pstr`) of the config file
+    #[clap(long)]
+    pub pstr: Option<String>,
+    /// Also reject a locally administered mac address
+    #[clap(long)]
+    pub strict_mac: bool,
+    /// Full json configuration export file name. The full json configuration includes also the
+    /// serial, edate and mac.
+    #[clap(long, value_parser, value_name = "EXPORT_CONFIG")]
+    pub export: Option<PathBuf>,
+    /// Compare the generated image with the content of an EEPROM device (e.g. the sysfs `eeprom`
+    /// file of the I2C EEPROM driver) instead of writing the output file. The exit code is 0 if the
+    /// device content matches the generated image and 1 otherwise.
+    #[clap(long, value_parser, value_name = "DEVICE")]
+    pub compare_with_device: Option<PathBuf>,
+    /// Write the generated image to an EEPROM device (e.g. the sysfs `eeprom` file of the I2C
+    /// EEPROM driver) instead of writing the output file. The capacity of the device is the size of
+    /// the device file or the `--size`; a larger image is not written.
+    #[clap(
+        long,
+        value_parser,
+        value_name = "DEVICE",
+        conflicts_with = "compare_with_device"
+    )]
+    pub write_device: Option<PathBuf>,
+    /// Abort reading the device and writing the output file with an error if the operation takes
+    /// longer than the given time in milliseconds. This prevents blocking forever on a missing
+    /// device or a stuck I2C bus.
+    #[clap(long, value_name = "MILLISECONDS")]
+    pub io_timeout_ms: Option<u64>,
+    /// Create the directory of the output file if it doesn't exist
+    #[clap(long)]
+    pub mkdir: bool,
+    /// EXPERIMENTAL: The byte order of the UUID in the vendor info atom. Only use `normal` to create
+    /// or read (`--decode`, `--compare-images`) images for interoperability tests, the HAT
+    /// specification requires `reversed`.
+    #[clap(long, value_enum, default_value = "reversed", hide = true)]
+    pub uuid_byte_order: UuidByteOrder,
+    /// Require that the given attributes are defined in the config file. It is an error if any of
+    /// them is taken from the program arguments, the environment or a default value.
+    #[clap(long, value_enum, value_delimiter = ',', value_name = "FIELDS")]
+    pub require: Vec<RequiredField>,
+    /// Print a compact identity string of the device to stdout (e.g. for a label with a QR code).
+    #[clap(long)]
+    pub identity: bool,
+    /// The fields of the identity string
+    #[clap(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "FIELDS",
+        default_value = "serial,mac,uuid,pr"
+    )]
+    pub identity_fields: Vec<IdentityField>,
+    /// The delimiter of the fields in the identity string
+    #[clap(long, value_name = "DELIMITER", default_value = "|")]
+    pub identity_delimiter: String,
+    /// JSON (or YAML) file with the configuration of gpio bank 0. It replaces the gpio bank 0 of
+    /// the config file.
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub gpio_bank0: Option<PathBuf>,
+    /// JSON (or YAML) file with the configuration of gpio bank 1. It replaces the gpio bank 1 of
+    /// the config file.
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub gpio_bank1: Option<PathBuf>,
+    /// The format of the output file
+    #[clap(long, value_enum, default_value = "bin")]
+    pub output_format: OutputFormat,
+    /// Print the CRC16 (CRC-16/ARC) of the image to stderr after it was written. The checksum is
+    /// calculated over the image without the padding.
+    #[clap(long)]
+    pub crc: bool,
+    /// Pad the image with `0xff` to the size of the EEPROM in bytes. It is an error if the image
+    /// is larger.
+    #[clap(long, value_parser = parse_prefixed_int::<usize>, value_name = "BYTES")]
+    pub size: Option<usize>,
+    /// Print a summary of the generated image to stderr
+    #[clap(long)]
+    pub summary: bool,
+    /// Only validate the config like `--check` and print the summary of the image to stdout. No
+    /// output file is written, so OUTPUT must not be given.
+    #[clap(
+        long,
+        conflicts_with_all = [
+            "summary", "identity", "export", "explain", "compare_with_device", "write_device",
+            "outfile_name"
+        ]
+    )]
+    pub summary_only: bool,
+    /// Print the atoms of the generated image with the config fields they are created from
+    #[clap(long)]
+    pub explain: bool,
+    /// Rebuild the image from a full config exported with `--export`. The serial, edate and mac are
+    /// taken from the config file only; the program arguments and the environment are ignored.
+    #[clap(
+        long,
+        conflicts_with_all = [
+            "serial", "edate", "mac", "pid", "pver", "prev", "vstr", "pstr", "gpio_bank0",
+            "gpio_bank1", "require"
+        ]
+    )]
+    pub rebuild: bool,
+    /// Compare the gpios of the config file with the ones of another config file and exit. All
+    /// added, removed and changed gpios are printed.
+    #[clap(long, value_parser, value_name = "OTHER_CONFIG")]
+    pub gpio_diff: Option<PathBuf>,
+    /// The output format of `--gpio-diff`
+    #[clap(long, value_enum, default_value = "text", requires = "gpio_diff")]
+    pub gpio_diff_format: DiffFormat,
+    /// Export the product identity (pid, pver, prev, vstr, pstr and dtstr) of the config file as
+    /// JSON and exit. The per unit serial, edate and mac are not included; the UUID is only
+    /// included if the config file defines the serial.
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub export_identity: Option<PathBuf>,
+    /// Report the number of atoms in the generated image and warn if it differs from the number
+    /// expected for the config
+    #[clap(long)]
+    pub count: bool,
+    /// Print the atoms of an existing image file in a human readable form and exit
+    #[clap(long, value_parser, value_name = "IMAGE")]
+    pub decode: Option<PathBuf>,
+    /// Print the decoded image of `--decode` as JSON: the header, the atoms and the reconstructed
+    /// RevPi config. Numeric fields are numbers and raw bytes are hex strings.
+    #[clap(long, requires = "decode")]
+    pub json_report: bool,
+    /// Compare two image files atom by atom and exit. The differing fields and gpios are printed;
+    /// the exit code is 0 if the images are equal and 1 otherwise.
+    #[clap(long, value_parser, num_args = 2, value_names = ["IMAGE_A", "IMAGE_B"])]
+    pub compare_images: Option<Vec<PathBuf>>,
+    /// Print the tool version and the supported format versions as JSON and exit
+    #[clap(long)]
+    pub version_info: bool,
+    /// Print the validations applied to the configuration and exit
+    #[clap(long)]
+    pub list_checks: bool,
+    /// Print the JSON schema of the config format and exit
+    #[clap(long)]
+    pub schema: bool,
+    /// Create this number of images with incrementing serial and mac (batch mode). The images are
+    /// named after OUTPUT, `{serial}` is replaced by the serial of the image; without it the serial
+    /// is appended to the file name (e.g. `out-4711.eep`).
+    #[clap(
+        long,
+        value_name = "COUNT",
+        requires_all = ["start_serial", "start_mac"],
+        conflicts_with_all = [
+            "serial", "mac", "rebuild", "identity", "export", "compare_with_device",
+            "write_device", "summary_only"
+        ]
+    )]
+    pub batch_count: Option<u32>,
+    /// The serial of the first image in batch mode
+    #[clap(long, value_parser = parse_prefixed_int::<u32>, requires = "batch_count")]
+    pub start_serial: Option<u32>,
+    /// The increment of the serial between the images in batch mode
+    #[clap(
+        long,
+        value_parser = parse_prefixed_int::<u32>,
+        default_value = "1",
+        requires = "batch_count"
+    )]
+    pub serial_step: u32,
+    /// The mac of the first image in batch mode, it is incremented by the `mac_count` (default: 1)
+    /// for every image
+    #[clap(long, value_parser = parse_mac, requires = "batch_count")]
+    pub start_mac: Option<MacAddr6>,
+    /// Only validate the config file (and the gpio bank files) and exit without writing the output
+    /// file
+    #[clap(long)]
+    pub check: bool,
+    /// Configuration file in JSON format or in YAML format with the extension `.yaml` or `.yml`
+    /// (`-` to read JSON from stdin)
+    #[clap(
+        value_parser,
+        value_name = "CONFIG",
+        required_unless_present_any = [
+            "list_checks", "version_info", "schema", "decode", "compare_images"
+        ]
+    )]
+    pub config: Option<PathBuf>,
+    /// Output file name (`-` to write the image to stdout)
+    #[clap(value_parser, value_name = "OUTPUT", default_value = "out.eep")]
+    pub outfile_name: PathBuf,
+}
+
+/// Compare the expected image with the actual content read from a device.
+///
+/// The device content may be longer than the image (the EEPROM is usually bigger than the data
+/// written to it), so only the first `expected.len()` bytes are compared. The function returns the
+/// offsets of all bytes that differ. If `actual` is shorter than `expected`, the missing bytes are
+/// reported as differing.
+fn diff_image(expected: &[u8], actual: &[u8]) -> Vec<usize> {
+    (0..expected.len())
+        .filter(|&i| actual.get(i) != Some(&expected[i]))
+        .collect()
+}
+
+#[test]
+fn test_diff_image() {
+    let expected = [0x52u8, 0x2d, 0x50, 0x69];
+    assert!(diff_image(&expected, &expected).is_empty());
+    assert!(diff_image(&expected, &[0x52, 0x2d, 0x50, 0x69, 0xff, 0xff]).is_empty());
+    assert_eq!(diff_image(&expected, &[0x52, 0x00, 0x50, 0x00]), vec![1, 3]);
+    assert_eq!(diff_image(&expected, &[0x52, 0x2d]), vec![2, 3]);
+}
+
+/// Run an I/O operation, but give up if it doesn't finish within `timeout`.
+///
+/// The operation is run in a separate thread. On expiry an error of kind
+/// [`io::ErrorKind::TimedOut`] is returned and the thread is left behind, because a blocking read
+/// or write on a stuck bus can't be cancelled. Without a timeout the operation is run directly.
+///
+/// The detached thread leaks with everything it owns (e.g. the open device and the image) until
+/// the operation returns or the process ends. So a timeout must be treated as fatal: every caller
+/// exits the process right after it, including the batch mode which stops at the first failed
+/// image instead of starting another thread for the next one.
+fn with_timeout<T, F>(timeout: Option<Duration>, op: F) -> io::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+{
+    let Some(timeout) = timeout else {
+        return op();
+    };
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        // The receiver is gone if the operation timed out, so the result is dropped
+        let _ = tx.send(op());
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("operation timed out after {} ms", timeout.as_millis()),
+        )),
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            Err(io::Error::other("operation aborted unexpectedly"))
+        }
+    }
+}
+
+#[test]
+fn test_with_timeout() {
+    assert_eq!(with_timeout(None, || Ok(42)).unwrap(), 42);
+    assert_eq!(
+        with_timeout(Some(Duration::from_secs(10)), || Ok(42)).unwrap(),
+        42
+    );
+    let e = with_timeout(Some(Duration::from_secs(10)), || -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "no device"))
+    })
+    .unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::NotFound);
+
+    // A stubbed backend that blocks longer than the timeout
+    let e = with_timeout(Some(Duration::from_millis(10)), || {
+        thread::sleep(Duration::from_secs(2));
+        Ok(42)
+    })
+    .unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+}
+
+/// Return the field of the image `buf` at `offset`, e.g. `header eeplen` or `atom 1 (type 0x0002)
+/// data`
+///
+/// The atoms are located by the dlen of their headers, so `buf` must be a valid image.
+fn image_field(buf: &[u8], offset: usize) -> String {
+    const HEADER_FIELDS: [(usize, &str); 5] = [
+        (4, "signature"),
+        (5, "version"),
+        (6, "reserved"),
+        (8, "numatoms"),
+        (12, "eeplen"),
+    ];
+    if let Some((_, name)) = HEADER_FIELDS.iter().find(|(end, _)| offset < *end) {
+        return format!("header {name}");
+    }
+    let mut start = 12;
+    let mut index = 0;
+    while start + 8 <= buf.len() {
+        let atype = u16::from_le_bytes([buf[start], buf[start + 1]]);
+        let dlen = u32::from_le_bytes(buf[start + 4..start + 8].try_into().unwrap()) as usize;
+        let end = start + 8 + dlen;
+        if offset < end {
+            // The dlen includes the CRC16 at the end of the atom
+            let field = match offset - start {
+                0..=1 => "type",
+                2..=3 => "count",
+                4..=7 => "dlen",
+                o if o + 2 < 8 + dlen => "data",
+                _ => "crc16",
+            };
+            return format!("atom {index} (type 0x{atype:04x}) {field}");
+        }
+        start = end;
+        index += 1;
+    }
+    "after the image".to_string()
+}
+
+#[test]
+fn test_image_field() {
+    // A header with one atom of type 0x0004 with 2 bytes of data
+    let mut buf = vec![0x52, 0x2d, 0x50, 0x69, 1, 0, 1, 0, 24, 0, 0, 0];
+    buf.extend_from_slice(&[4, 0, 0, 0, 4, 0, 0, 0, 0x34, 0x32, 0xaa, 0xbb]);
+    assert_eq!(image_field(&buf, 0), "header signature");
+    assert_eq!(image_field(&buf, 4), "header version");
+    assert_eq!(image_field(&buf, 7), "header numatoms");
+    assert_eq!(image_field(&buf, 11), "header eeplen");
+    assert_eq!(image_field(&buf, 12), "atom 0 (type 0x0004) type");
+    assert_eq!(image_field(&buf, 14), "atom 0 (type 0x0004) count");
+    assert_eq!(image_field(&buf, 19), "atom 0 (type 0x0004) dlen");
+    assert_eq!(image_field(&buf, 20), "atom 0 (type 0x0004) data");
+    assert_eq!(image_field(&buf, 21), "atom 0 (type 0x0004) data");
+    assert_eq!(image_field(&buf, 22), "atom 0 (type 0x0004) crc16");
+    assert_eq!(image_field(&buf, 23), "atom 0 (type 0x0004) crc16");
+    assert_eq!(image_field(&buf, 24), "after the image");
+}
+
+/// Compare the generated image with the content of a device and return `true` if they are equal
+///
+/// If the device content can be decoded, the differences are printed field by field like
+/// `--compare-images` does. Otherwise the differing header and atom fields are printed, followed
+/// by the first differing bytes.
+fn compare_with_device(
+    buf: &[u8],
+    eep: &Eep,
+    uuid_byte_order: UuidByteOrder,
+    device: &Path,
+    timeout: Option<Duration>,
+) -> bool {
+    // Limit the number of reported bytes, e.g. an empty EEPROM would differ in every byte
+    const MAX_REPORTED: usize = 16;
+
+    let path = device.to_path_buf();
+    let actual = match with_timeout(timeout, move || std::fs::read(path)) {
+        Ok(actual) => actual,
+        Err(e) => {
+            eprintln!(
+                "ERROR: Can't read device `{}': {e}",
+                device.to_string_lossy()
+            );
+            process::exit(1);
+        }
+    };
+    let diff = diff_image(buf, &actual);
+    let decoded =
+        match Eep::from_bytes_with_uuid_byte_order(trim_to_eeplen(&actual), uuid_byte_order.into())
+        {
+            Ok(actual_eep) => compare_images(eep, &actual_eep),
+            Err(_) => Vec::new(),
+        };
+    for line in &decoded {
+        println!("{line}");
+    }
+    if actual.len() < buf.len() {
+        println!(
+            "Device content is too short: {} bytes (expected at least {} bytes)",
+            actual.len(),
+            buf.len()
+        );
+    }
+    // Fall back to the raw fields and bytes if the device content can't be decoded (or only
+    // differs in e.g. the encoding of a field)
+    let raw = if decoded.is_empty() { diff.len() } else { 0 };
+    let mut fields: Vec<(String, usize)> = Vec::new();
+    for &offset in diff.iter().take(raw) {
+        let field = image_field(buf, offset);
+        match fields.last_mut() {
+            Some((last, count)) if *last == field => *count += 1,
+            _ => fields.push((field, 1)),
+        }
+    }
+    for (field, count) in &fields {
+        println!("{field}: {count} byte(s) differ");
+    }
+    for &offset in diff.iter().take(raw.min(MAX_REPORTED)) {
+        match actual.get(offset) {
+            Some(found) => println!(
+                "  offset 0x{offset:04x}: expected 0x{:02x}, found 0x{found:02x}",
+                buf[offset]
+            ),
+            None => println!(
+                "  offset 0x{offset:04x}: expected 0x{:02x}, found nothing",
+                buf[offset]
+            ),
+        }
+    }
+    if raw > MAX_REPORTED {
+        println!("  ... and {} more", raw - MAX_REPORTED);
+    }
+    if diff.is_empty() {
+        println!(
+            "PASS: device `{}' matches the generated image ({} bytes)",
+            device.to_string_lossy(),
+            buf.len()
+        );
+        true
+    } else {
+        println!(
+            "FAIL: device `{}' differs from the generated image in {} of {} bytes",
+            device.to_string_lossy(),
+            diff.len(),
+            buf.len()
+        );
+        false
+    }
+}
+
+/// Check that an image of `len` bytes fits into a device with `capacity` bytes
+fn check_capacity(len: usize, capacity: usize) -> Result<(), String> {
+    if capacity == 0 {
+        return Err("can't determine the size of the device (use --size)".to_string());
+    }
+    if len > capacity {
+        return Err(format!(
+            "the image ({len} bytes) is larger than the device ({capacity} bytes)"
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_check_capacity() {
+    assert_eq!(check_capacity(278, 278), Ok(()));
+    assert_eq!(check_capacity(278, 4096), Ok(()));
+    assert_eq!(
+        check_capacity(278, 256),
+        Err("the image (278 bytes) is larger than the device (256 bytes)".to_string())
+    );
+    assert!(check_capacity(278, 0).is_err());
+}
+
+/// Write the image to an EEPROM device and return the number of written bytes
+///
+/// The `size` overrides the capacity of the device, which is otherwise the size of the device
+/// file. The `timeout` covers all accesses to the device: opening it, probing its size and
+/// writing the image.
+fn write_device(
+    buf: Vec<u8>,
+    device: &Path,
+    size: Option<usize>,
+    timeout: Option<Duration>,
+) -> io::Result<usize> {
+    let path = device.to_path_buf();
+    with_timeout(timeout, move || {
+        let mut device = OpenOptions::new().write(true).open(path)?;
+        let capacity = match size {
+            Some(size) => size,
+            None => device.metadata()?.len() as usize,
+        };
+        check_capacity(buf.len(), capacity).map_err(io::Error::other)?;
+        device.write_all(&buf)?;
+        device.flush()?;
+        Ok(buf.len())
+    })
+}
+
+/// The fields which can be included in the identity string (see [Cli::identity])
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum IdentityField {
+    /// the serial number
+    Serial,
+    /// the (first) mac address
+    Mac,
+    /// the UUID of the device
+    Uuid,
+    /// the part number (PR number incl. the product revision, e.g. PR100302R01)
+    Pr,
+    /// the end test date
+    Edate,
+}
+
+/// Create a compact identity string of the device, e.g. to be encoded into a QR code.
+///
+/// The `config` must contain the serial, edate and mac.
+fn identity_string(config: &RevPiHatEeprom, fields: &[IdentityField], delimiter: &str) -> String {
+    let serial = config
+        .serial
+        .expect("BUG: Missing serial in RevPiHatEeprom configuration");
+    fields
+        .iter()
+        .map(|field| match field {
+            IdentityField::Serial => serial.to_string(),
+            IdentityField::Mac => config
+                .mac
+                .expect("BUG: Missing mac address in RevPiHatEeprom configuration")
+                .to_string(),
+            IdentityField::Uuid => {
+                revpi_hat_eep::derive_uuid(config.pid, config.pver, config.prev, serial).to_string()
+            }
+            IdentityField::Pr => revpi_hat_eep::part_number(config.pid, config.prev),
+            IdentityField::Edate => config
+                .edate
+                .expect("BUG: Missing end test date in RevPiHatEeprom configuration")
+                .to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join(delimiter)
+}
+
+#[test]
+fn test_identity_string() {
+    let mut config = test_config();
+    config.serial = Some(4711);
+    let uuid = revpi_hat_eep::derive_uuid(config.pid, config.pver, config.prev, 4711);
+
+    assert_eq!(
+        identity_string(
+            &config,
+            &[
+                IdentityField::Serial,
+                IdentityField::Mac,
+                IdentityField::Uuid,
+                IdentityField::Pr
+            ],
+            "|"
+        ),
+        format!("4711|C8:3E:A7:00:00:01|{uuid}|PR100666R03")
+    );
+    assert_eq!(
+        identity_string(&config, &[IdentityField::Edate, IdentityField::Serial], ";"),
+        "2024-01-01;4711"
+    );
+}
+
+/// The output format of `--gpio-diff`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DiffFormat {
+    /// one line per gpio
+    Text,
+    /// a JSON array
+    Json,
+}
+
+/// The format of the output file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// the raw image
+    Bin,
+    /// Intel HEX records
+    Ihex,
+    /// a C byte array
+    Carray,
+}
+
+/// Append an Intel HEX record incl. its checksum to `out`
+fn ihex_record(out: &mut String, rtype: u8, address: u16, data: &[u8]) {
+    let mut record = vec![data.len() as u8];
+    record.extend_from_slice(&address.to_be_bytes());
+    record.push(rtype);
+    record.extend_from_slice(data);
+    let checksum = record.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+    record.push(checksum.wrapping_neg());
+    out.push(':');
+    out.push_str(&hex_string(&record).to_uppercase());
+    out.push('\n');
+}
+
+/// Encode `data` as Intel HEX
+///
+/// The data is split into data records of 16 bytes. Above 64 KiB an extended linear address
+/// record sets the upper 16 bits of the address. The end of file record terminates the output.
+fn to_ihex(data: &[u8]) -> String {
+    let mut out = String::new();
+    for (n, chunk) in data.chunks(16).enumerate() {
+        let address = n * 16;
+        if address > 0 && address % 0x10000 == 0 {
+            ihex_record(&mut out, 0x04, 0, &((address >> 16) as u16).to_be_bytes());
+        }
+        ihex_record(&mut out, 0x00, address as u16, chunk);
+    }
+    ihex_record(&mut out, 0x01, 0, &[]);
+    out
+}
+
+#[test]
+fn test_to_ihex() {
+    assert_eq!(to_ihex(&[]), ":00000001FF\n");
+    assert_eq!(to_ihex(b"R-Pi"), ":04000000522D5069C4\n:00000001FF\n");
+
+    // Decode the records and check the checksums
+    let decode = |ihex: &str| {
+        let mut data = Vec::new();
+        let mut upper = 0;
+        for line in ihex.lines() {
+            let record: Vec<u8> = (1..line.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&line[i..i + 2], 16).unwrap())
+                .collect();
+            assert!(line.starts_with(':'));
+            assert_eq!(record[0] as usize, record.len() - 5);
+            assert_eq!(record.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)), 0);
+            let payload = &record[4..record.len() - 1];
+            match record[3] {
+                0x00 => {
+                    let address = upper + u16::from_be_bytes([record[1], record[2]]) as usize;
+                    assert_eq!(address, data.len());
+                    data.extend_from_slice(payload);
+                }
+                0x01 => return data,
+                0x04 => upper = (u16::from_be_bytes([payload[0], payload[1]]) as usize) << 16,
+                rtype => panic!("unexpected record type: {rtype}"),
+            }
+        }
+        panic!("missing end of file record");
+    };
+
+    let config = test_config();
+    let mut buf: Vec<u8> = Vec::new();
+    create_rpi_eep(&config, UuidByteOrder::Reversed)
+        .unwrap()
+        .to_bytes(&mut buf);
+    assert_eq!(decode(&to_ihex(&buf)), buf);
+
+    let big: Vec<u8> = (0..0x10010).map(|n| n as u8).collect();
+    let ihex = to_ihex(&big);
+    assert!(ihex.contains("\n:020000040001F9\n"));
+    assert_eq!(decode(&ihex), big);
+}
+
+/// Return the number of `0xff` bytes to pad an image of `len` bytes to `size` bytes
+fn padding(len: usize, size: usize) -> Result<usize, String> {
+    size.checked_sub(len).ok_or_else(|| {
+        format!("The image ({len} bytes) is larger than the EEPROM size ({size} bytes)")
+    })
+}
+
+#[test]
+fn test_padding() {
+    assert_eq!(padding(278, 278), Ok(0));
+    assert_eq!(padding(278, 4096), Ok(3818));
+    assert_eq!(
+        padding(278, 256),
+        Err("The image (278 bytes) is larger than the EEPROM size (256 bytes)".to_string())
+    );
+}
+
+/// Return the padding of an image of `len` bytes to the `size` (if given) or exit
+fn padding_or_exit(len: usize, size: Option<usize>) -> usize {
+    match size.map(|size| padding(len, size)) {
+        Some(Ok(padding)) => padding,
+        Some(Err(e)) => {
+            eprintln!("ERROR: {e}");
+            process::exit(1);
+        }
+        None => 0,
+    }
+}
+
+/// Format `data` as C byte array `revpi_eeprom` with 12 bytes per line and its length
+fn to_c_array(data: &[u8]) -> String {
+    let mut out = String::from("const unsigned char revpi_eeprom[] = {\n");
+    for chunk in data.chunks(12) {
+        let line: Vec<String> = chunk.iter().map(|b| format!("0x{b:02x},")).collect();
+        out.push_str("    ");
+        out.push_str(&line.join(" "));
+        out.push('\n');
+    }
+    out.push_str("};\n");
+    out.push_str(&format!(
+        "const unsigned int revpi_eeprom_len = {};\n",
+        data.len()
+    ));
+    out
+}
+
+#[test]
+fn test_to_c_array() {
+    let data: Vec<u8> = (0..14).collect();
+    assert_eq!(
+        to_c_array(&data),
+        "const unsigned char revpi_eeprom[] = {\n    \
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b,\n    \
+        0x0c, 0x0d,\n\
+        };\n\
+        const unsigned int revpi_eeprom_len = 14;\n"
+    );
+    assert_eq!(
+        to_c_array(&[]),
+        "const unsigned char revpi_eeprom[] = {\n};\nconst unsigned int revpi_eeprom_len = 0;\n"
+    );
+}
+
+/// Environment variable to supply the serial (see [Cli::serial])
+const ENV_SERIAL: &str = "REVPI_SERIAL";
+/// Environment variable to supply the end test date (see [Cli::edate])
+const ENV_EDATE: &str = "REVPI_EDATE";
+/// Environment variable to supply the (first) mac address (see [Cli::mac])
+const ENV_MAC: &str = "REVPI_MAC";
+
+/// The source a value was taken from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ValueSource {
+    /// The value was given as program argument
+    Cli,
+    /// The value was given as environment variable
+    Env,
+    /// The value was given in the config file
+    Config,
+    /// The value was not given at all and a default value is used
+    Default,
+}
+
+impl std::fmt::Display for ValueSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ValueSource::Cli => "program arguments",
+            ValueSource::Env => "environment",
+            ValueSource::Config => "config file",
+            ValueSource::Default => "default value",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Attributes which can be required to be defined in the config file (see [Cli::require])
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum RequiredField {
+    Serial,
+    Edate,
+    Mac,
+}
+
+impl std::fmt::Display for RequiredField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            RequiredField::Serial => "serial",
+            RequiredField::Edate => "edate",
+            RequiredField::Mac => "mac",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Return the required attributes which are not taken from the config file together with their
+/// actual source.
+fn missing_required_fields(
+    required: &[RequiredField],
+    sources: &[(RequiredField, ValueSource)],
+) -> Vec<(RequiredField, ValueSource)> {
+    sources
+        .iter()
+        .filter(|(field, source)| required.contains(field) && *source != ValueSource::Config)
+        .copied()
+        .collect()
+}
+
+#[test]
+fn test_missing_required_fields() {
+    let sources = [
+        (RequiredField::Serial, ValueSource::Config),
+        (RequiredField::Edate, ValueSource::Default),
+        (RequiredField::Mac, ValueSource::Cli),
+    ];
+    assert!(missing_required_fields(&[], &sources).is_empty());
+    assert!(missing_required_fields(&[RequiredField::Serial], &sources).is_empty());
+    assert_eq!(
+        missing_required_fields(
+            &[
+                RequiredField::Serial,
+                RequiredField::Edate,
+                RequiredField::Mac
+            ],
+            &sources
+        ),
+        vec![
+            (RequiredField::Edate, ValueSource::Default),
+            (RequiredField::Mac, ValueSource::Cli)
+        ]
+    );
+}
+
+/// Read and parse the value of an environment variable.
+///
+/// The variable is looked up with `env` (usually [std::env::var()]). Returns `None` if the variable
+/// is not set. If the variable is set but can't be parsed the program is terminated with an error.
+fn env_value<T, E, F>(env: E, var: &str, parser: F) -> Option<T>
+where
+    E: Fn(&str) -> Result<String, std::env::VarError>,
+    F: Fn(&str) -> Result<T, String>,
+{
+    let val = match env(var) {
+        Ok(val) => val,
+        Err(std::env::VarError::NotPresent) => return None,
+        Err(e) => {
+            eprintln!("ERROR: Invalid value of the environment variable `{var}': {e}");
+            process::exit(1);
+        }
+    };
+    match parser(&val) {
+        Ok(val) => Some(val),
+        Err(e) => {
+            eprintln!("ERROR: Invalid value of the environment variable `{var}' (`{val}'): {e}");
+            process::exit(1);
+        }
+    }
+}
+
+#[test]
+fn test_env_value() {
+    let env = test_env(&[("REVPI_TEST_ENV_VALUE", "0x10")]);
+    assert_eq!(
+        env_value(&env, "REVPI_TEST_ENV_VALUE", parse_prefixed_int::<u32>),
+        Some(16)
+    );
+    assert_eq!(
+        env_value(&env, "REVPI_TEST_ENV_OTHER", parse_prefixed_int::<u32>),
+        None
+    );
+}
+
+/// Return a lookup of environment variables for the tests that only knows `vars`
+#[cfg(test)]
+fn test_env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Result<String, std::env::VarError> {
+    let vars: Vec<(String, String)> = vars
+        .iter()
+        .map(|(var, val)| (var.to_string(), val.to_string()))
+        .collect();
+    move |var| {
+        vars.iter()
+            .find(|(name, _)| name == var)
+            .map(|(_, val)| val.clone())
+            .ok_or(std::env::VarError::NotPresent)
+    }
+}
+
+/// Determine the value of an attribute which can be given by different sources.
+///
+/// The precedence is: program arguments > environment variable > config file. The value of the
+/// environment variable `env_var` is looked up with `env`. A warning is printed for every value
+/// that is overridden by a source with a higher precedence.
+fn resolve_value<T, E>(
+    name: &str,
+    env_var: &str,
+    cli: Option<T>,
+    env: E,
+    config: Option<T>,
+) -> Option<(T, ValueSource)>
+where
+    T: std::fmt::Display,
+    E: FnOnce(&str) -> Option<T>,
+{
+    let env = env(env_var);
+    if let Some(cli) = cli {
+        if let Some(env) = env {
+            eprintln!(
+                "WARNING: Overriding {name} from the environment (`{env_var}`: `{env}`) \
+                with the {name} from the program arguments (`{cli}`)."
+            );
+        }
+        if let Some(config) = config {
+            eprintln!(
+                "WARNING: Overriding {name} from the config file (`{config}`) \
+                with the {name} from the program arguments (`{cli}`)."
+            );
+        }
+        Some((cli, ValueSource::Cli))
+    } else if let Some(env) = env {
+        if let Some(config) = config {
+            eprintln!(
+                "WARNING: Overriding {name} from the config file (`{config}`) \
+                with the {name} from the environment (`{env_var}`: `{env}`)."
+            );
+        }
+        Some((env, ValueSource::Env))
+    } else {
+        config.map(|config| (config, ValueSource::Config))
+    }
+}
+
+#[test]
+fn test_resolve_value() {
+    assert_eq!(
+        resolve_value("serial", ENV_SERIAL, Some(1), |_| Some(2), Some(3)),
+        Some((1, ValueSource::Cli))
+    );
+    assert_eq!(
+        resolve_value("serial", ENV_SERIAL, Some(1), |_| None, None),
+        Some((1, ValueSource::Cli))
+    );
+    assert_eq!(
+        resolve_value("serial", ENV_SERIAL, None, |_| Some(2), Some(3)),
+        Some((2, ValueSource::Env))
+    );
+    assert_eq!(
+        resolve_value("serial", ENV_SERIAL, None, |_| Some(2), None),
+        Some((2, ValueSource::Env))
+    );
+    assert_eq!(
+        resolve_value("serial", ENV_SERIAL, None, |_| None, Some(3)),
+        Some((3, ValueSource::Config))
+    );
+    assert_eq!(
+        resolve_value::<u32, _>("serial", ENV_SERIAL, None, |_| None, None),
+        None
+    );
+}
+
+fn export_config(config: &RevPiHatEeprom, export_path: PathBuf) {
+    let json = serde_json::to_string(config)
+        .expect("BUG: Can't create (full) json from RevPiHatEeprom config");
+    let mut export_file = match OpenOptions::new()
+        .read(false)
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .open(&export_path)
+    {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!(
+                "ERROR: Can't open json export file: `{}': {e}",
+                export_path.to_string_lossy()
+            );
+            process::exit(1);
+        }
+    };
+    if let Err(e) = export_file.write_all(json.as_bytes()) {
+        eprintln!(
+            "ERROR: Can't write to file `{}`: {e}",
+            export_path.to_string_lossy()
+        );
+        process::exit(1);
+    }
+}
+
+/// Return the versions supported by this tool (see `--version-info`)
+fn version_info() -> serde_json::Value {
+    serde_json::json!({
+        "tool_version": env!("CARGO_PKG_VERSION"),
+        "eeprom_header_version": rpi_hat_eep::EEP_VERSION,
+        "format_versions": revpi_hat_eep::SUPPORTED_FORMAT_VERSIONS,
+        "custom_atoms": RevPiCustomField::ALL.len(),
+        "max_eeprom_data_version": revpi_hat_eep::EEPROM_DATA_VERSION_MAX,
+    })
+}
+
+#[test]
+fn test_version_info() {
+    let info = version_info();
+    assert_eq!(info["tool_version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(info["eeprom_header_version"], 1);
+    assert_eq!(info["format_versions"], serde_json::json!([1]));
+    assert_eq!(info["custom_atoms"], 7);
+    assert_eq!(info["max_eeprom_data_version"], u16::MAX);
+}
+
+#[cfg(feature = "schema")]
+fn print_schema() {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&revpi_hat_eep::config_schema())
+            .expect("BUG: Can't serialize the config schema")
+    );
+}
+
+#[cfg(not(feature = "schema"))]
+fn print_schema() {
+    eprintln!("ERROR: revpi-eep was built without the `schema` feature");
+    process::exit(1);
+}
+
+/// Print the validation rules with their severity and if `--strict-mac` makes them fatal
+fn list_checks() {
+    for line in list_checks_lines() {
+        println!("{line}");
+    }
+}
+
+fn list_checks_lines() -> Vec<String> {
+    let rules = revpi_hat_eep::validation_rules();
+    let id_width = rules.iter().map(|r| r.id.len()).max().unwrap_or(0);
+    rules
+        .iter()
+        .map(|rule| {
+            format!(
+                "{:id_width$}  {:7}  {:12}  {}",
+                rule.id,
+                rule.severity.to_string(),
+                if rule.strict { "--strict-mac" } else { "-" },
+                rule.description
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_list_checks() {
+    let lines = list_checks_lines();
+    assert_eq!(lines.len(), revpi_hat_eep::validation_rules().len());
+    let local = lines
+        .iter()
+        .find(|line| line.starts_with("mac-local "))
+        .unwrap();
+    assert!(local.contains("  warning  --strict-mac  "));
+    let version = lines
+        .iter()
+        .find(|line| line.starts_with("format-version "))
+        .unwrap();
+    assert!(version.contains("  error    -             "));
+}
+
+/// Replace `config` with the value from the program arguments (if given)
+fn override_value<T>(name: &str, cli: Option<T>, config: &mut T)
+where
+    T: std::fmt::Display,
+{
+    if let Some(cli) = cli {
+        eprintln!(
+            "WARNING: Overriding {name} from the config file (`{config}`) \
+            with the {name} from the program arguments (`{cli}`)."
+        );
+        *config = cli;
+    }
+}
+
+/// Override the product identity of the config with the program arguments
+///
+/// The result has to be validated again, see [RevPiHatEeprom::validate()].
+fn override_product(cli: &Cli, config: &mut RevPiHatEeprom) {
+    override_value("pid", cli.pid, &mut config.pid);
+    override_value("pver", cli.pver, &mut config.pver);
+    override_value("prev", cli.prev, &mut config.prev);
+    override_value("vstr", cli.vstr.clone(), &mut config.vstr);
+    override_value("pstr", cli.pstr.clone(), &mut config.pstr);
+    match (cli.mac_count, config.mac_count.as_mut()) {
+        (Some(cli), Some(config)) => override_value("mac_count", Some(cli), config),
+        (Some(cli), None) => config.mac_count = Some(cli),
+        (None, _) => {}
+    }
+}
+
+#[test]
+fn test_override_product() {
+    let mut config = test_config();
+    let cli = Cli::parse_from([
+        "revpi-eep",
+        "--pid",
+        "0x12e",
+        "--pstr",
+        "RevPi Variant",
+        "c",
+    ]);
+    override_product(&cli, &mut config);
+    assert_eq!(config.pid, 302);
+    assert_eq!(config.pstr, "RevPi Variant");
+    // Not overridden
+    assert_eq!(config.pver, 333);
+    assert_eq!(config.prev, 3);
+    assert_eq!(config.vstr, "KUNBUS GmbH");
+}
+
+#[test]
+fn test_summary_flags() {
+    let cli = Cli::parse_from(["revpi-eep", "c"]);
+    assert!(!cli.summary);
+    let cli = Cli::parse_from(["revpi-eep", "--summary", "c"]);
+    assert!(cli.summary);
+    let cli = Cli::parse_from(["revpi-eep", "--summary-only", "c"]);
+    assert!(cli.summary_only);
+    for args in [
+        &["revpi-eep", "--summary-only", "c", "-"][..],
+        &["revpi-eep", "--summary-only", "c", "out.eep"],
+        &["revpi-eep", "--summary-only", "--identity", "c"],
+        &["revpi-eep", "--summary-only", "--summary", "c"],
+    ] {
+        let e = Cli::try_parse_from(args).err().unwrap();
+        assert_eq!(e.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+}
+
+/// Set the serial, edate and mac of the config from the program arguments, the environment and
+/// the config file (in this order of precedence)
+///
+/// The environment variables are looked up with `env` (usually [std::env::var()]).
+fn resolve_sources<E>(cli: &Cli, config: &mut RevPiHatEeprom, env: E)
+where
+    E: Fn(&str) -> Result<String, std::env::VarError>,
+{
+    let serial_env = |var: &str| env_value(&env, var, parse_prefixed_int::<u32>);
+    let (serial, serial_source) =
+        match resolve_value("serial", ENV_SERIAL, cli.serial, serial_env, config.serial) {
+            Some(serial) => serial,
+            None => {
+                eprintln!(
+                    "ERROR: The `serial` was neither specified as argument, \
+                    in the environment (`{ENV_SERIAL}`) nor in the config file."
+                );
+                process::exit(1);
+            }
+        };
+
+    let edate_env = |var: &str| env_value(&env, var, parse_edate);
+    let (edate, edate_source) =
+        match resolve_value("edate", ENV_EDATE, cli.edate, edate_env, config.edate) {
+            Some(edate) => edate,
+            None => (chrono::Local::now().date_naive(), ValueSource::Default),
+        };
+
+    let mac_env = |var: &str| env_value(&env, var, parse_mac);
+    let (mac, mac_source) = match resolve_value("mac", ENV_MAC, cli.mac, mac_env, config.mac) {
+        Some(mac) => mac,
+        None => {
+            eprintln!(
+                "ERROR: The `mac` was neither specified as argument, \
+                in the environment (`{ENV_MAC}`) nor in the config file."
+            );
+            process::exit(1);
+        }
+    };
+
+    let missing = missing_required_fields(
+        &cli.require,
+        &[
+            (RequiredField::Serial, serial_source),
+            (RequiredField::Edate, edate_source),
+            (RequiredField::Mac, mac_source),
+        ],
+    );
+    if !missing.is_empty() {
+        for (field, source) in missing {
+            eprintln!(
+                "ERROR: The required `{field}` is not defined in the config file (taken from: {source})."
+            );
+        }
+        process::exit(1);
+    }
+
+    config.serial = Some(serial);
+    config.edate = Some(edate);
+    config.mac = Some(mac);
+}
+
+#[test]
+fn test_resolve_sources() {
+    let mut config = test_config();
+    config.serial = Some(3);
+    config.edate = Some(NaiveDate::from_ymd_opt(2024, 1, 3).unwrap());
+    config.mac = Some("c8:3e:a7:00:00:03".parse().unwrap());
+    let env = test_env(&[
+        (ENV_SERIAL, "2"),
+        (ENV_EDATE, "2024-01-02"),
+        (ENV_MAC, "c8:3e:a7:00:00:02"),
+    ]);
+
+    // The config file only
+    let cli = Cli::parse_from(["revpi-eep", "c"]);
+    let mut resolved = config.clone();
+    resolve_sources(&cli, &mut resolved, test_env(&[]));
+    assert_eq!(resolved.serial, Some(3));
+    assert_eq!(resolved.edate, config.edate);
+    assert_eq!(resolved.mac, config.mac);
+
+    // The environment overrides the config file
+    let mut resolved = config.clone();
+    resolve_sources(&cli, &mut resolved, &env);
+    assert_eq!(resolved.serial, Some(2));
+    assert_eq!(resolved.edate, NaiveDate::from_ymd_opt(2024, 1, 2));
+    assert_eq!(resolved.mac, Some("c8:3e:a7:00:00:02".parse().unwrap()));
+
+    // The program arguments override the environment
+    let cli = Cli::parse_from([
+        "revpi-eep",
+        "--serial",
+        "1",
+        "--edate",
+        "2024-01-01",
+        "--mac",
+        "c8:3e:a7:00:00:01",
+        "c",
+    ]);
+    let mut resolved = config.clone();
+    resolve_sources(&cli, &mut resolved, &env);
+    assert_eq!(resolved.serial, Some(1));
+    assert_eq!(resolved.edate, NaiveDate::from_ymd_opt(2024, 1, 1));
+    assert_eq!(resolved.mac, Some("c8:3e:a7:00:00:01".parse().unwrap()));
+}
+
+/// Print the gpio differences between the config and another config file
+fn print_gpio_diff(config: &RevPiHatEeprom, other_path: &Path, format: DiffFormat) {
+    let other = match std::fs::read_to_string(other_path)
+        .map_err(|e| e.to_string())
+        .and_then(|other| {
+            let format = ConfigFormat::from_path(other_path);
+            revpi_hat_eep::parse_config_files((&other, format), None, None)
+                .map_err(|e| e.to_string())
+        }) {
+        Ok(other) => other,
+        Err(e) => {
+            eprintln!(
+                "ERROR: Invalid config file `{}': {e}",
+                other_path.to_string_lossy()
+            );
+            process::exit(1);
+        }
+    };
+    let diff = revpi_hat_eep::gpio::gpio_diff(&config.gpiobanks, &other.gpiobanks);
+    match format {
+        DiffFormat::Text => {
+            for d in diff {
+                println!("{d}");
+            }
+        }
+        DiffFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&diff).expect("BUG: Can't serialize the gpio diff")
+        ),
+    }
+}
+
+/// The stable identity of a product, without the data of a single unit (see `--export-identity`)
+#[derive(Debug, serde::Serialize)]
+struct ProductIdentity<'a> {
+    pid: u16,
+    pver: u16,
+    prev: u16,
+    vstr: &'a str,
+    pstr: &'a str,
+    dtstr: &'a str,
+    /// The UUID depends on the serial, so it is only included if the config file defines a serial
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uuid: Option<uuid::Uuid>,
+}
+
+impl<'a> ProductIdentity<'a> {
+    fn new(config: &'a RevPiHatEeprom) -> Self {
+        ProductIdentity {
+            pid: config.pid,
+            pver: config.pver,
+            prev: config.prev,
+            vstr: &config.vstr,
+            pstr: &config.pstr,
+            dtstr: &config.dtstr,
+            uuid: config.serial.map(|serial| {
+                revpi_hat_eep::derive_uuid(config.pid, config.pver, config.prev, serial)
+            }),
+        }
+    }
+}
+
+#[test]
+fn test_product_identity() {
+    let mut config = test_config();
+    // The uuid is only part of the identity with a serial
+    config.serial = None;
+    let json = serde_json::to_value(ProductIdentity::new(&config)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "pid": 666,
+            "pver": 333,
+            "prev": 3,
+            "vstr": "KUNBUS GmbH",
+            "pstr": "RevPi ExampleDevice 8GB",
+            "dtstr": "revpi-example-2022"
+        })
+    );
+
+    config.serial = Some(1);
+    let json = serde_json::to_value(ProductIdentity::new(&config)).unwrap();
+    assert_eq!(json["uuid"], "aba68c9a-1352-3d5d-8548-b813bc0ad5fb");
+}
+
+fn export_identity(config: &RevPiHatEeprom, path: &Path) {
+    let json = serde_json::to_string_pretty(&ProductIdentity::new(config))
+        .expect("BUG: Can't create json from the product identity");
+    if let Err(e) = std::fs::write(path, json) {
+        eprintln!(
+            "ERROR: Can't write identity file `{}': {e}",
+            path.to_string_lossy()
+        );
+        process::exit(1);
+    }
+}
+
+/// Make sure a config used with `--rebuild` contains all fields of a full export
+fn check_full_config(config: &RevPiHatEeprom, config_path: &Path) {
+    let missing: Vec<&str> = [
+        ("serial", config.serial.is_none()),
+        ("edate", config.edate.is_none()),
+        ("mac", config.mac.is_none()),
+    ]
+    .into_iter()
+    .filter_map(|(field, missing)| missing.then_some(field))
+    .collect();
+    if !missing.is_empty() {
+        eprintln!(
+            "ERROR: The config file `{}' is not a full export, missing: {}",
+            config_path.to_string_lossy(),
+            missing.join(", ")
+        );
+        process::exit(1);
+    }
+}
+
+/// Check that the directory of the output file exists or create it if `mkdir` is set
+fn prepare_output_dir(outfile_name: &Path, mkdir: bool) -> Result<(), String> {
+    let dir = match outfile_name.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => return Ok(()),
+    };
+    if dir.is_dir() {
+        return Ok(());
+    }
+    if !mkdir {
+        return Err(format!(
+            "output directory `{}' does not exist (use --mkdir to create it)",
+            dir.to_string_lossy()
+        ));
+    }
+    std::fs::create_dir_all(dir).map_err(|e| {
+        format!(
+            "Can't create output directory `{}': {e}",
+            dir.to_string_lossy()
+        )
+    })
+}
+
+/// Exit with an error if the directory of the output file can't be used
+fn prepare_output_dir_or_exit(outfile_name: &Path, mkdir: bool) {
+    if let Err(e) = prepare_output_dir(outfile_name, mkdir) {
+        eprintln!("ERROR: {e}");
+        process::exit(1);
+    }
+}
+
+#[test]
+fn test_prepare_output_dir() {
+    let base = std::env::temp_dir().join(format!("revpi-eep-test-outdir-{}", process::id()));
+    let outfile = base.join("product").join("out.eep");
+    assert_eq!(prepare_output_dir(Path::new("out.eep"), false), Ok(()));
+    assert_eq!(
+        prepare_output_dir(&outfile, false),
+        Err(format!(
+            "output directory `{}' does not exist (use --mkdir to create it)",
+            base.join("product").to_string_lossy()
+        ))
+    );
+    assert!(!base.exists());
+
+    assert_eq!(prepare_output_dir(&outfile, true), Ok(()));
+    assert!(base.join("product").is_dir());
+    // An existing directory is fine without --mkdir
+    assert_eq!(prepare_output_dir(&outfile, false), Ok(()));
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+/// Read the config file, `-` reads the config from stdin
+fn read_config(path: &Path) -> io::Result<String> {
+    if path == Path::new("-") {
+        io::read_to_string(io::stdin())
+    } else {
+        std::fs::read_to_string(path)
+    }
+}
+
+/// Return the serial and mac of every image in batch mode
+///
+/// The mac is incremented by `mac_step`, the number of mac addresses of a device.
+fn batch_items(
+    start_serial: u32,
+    serial_step: u32,
+    start_mac: MacAddr6,
+    mac_step: u8,
+    count: u32,
+) -> Result<Vec<(u32, MacAddr6)>, String> {
+    (0..count)
+        .map(|n| {
+            let serial = n
+                .checked_mul(serial_step)
+                .and_then(|offset| start_serial.checked_add(offset))
+                .ok_or_else(|| format!("The serial of image {} overflows", n + 1))?;
+            let mac = revpi_hat_eep::mac_add(start_mac, u64::from(n) * u64::from(mac_step))
+                .ok_or_else(|| format!("The mac address of image {} overflows", n + 1))?;
+            Ok((serial, mac))
+        })
+        .collect()
+}
+
+#[test]
+fn test_batch_items() {
+    let mac = |s: &str| s.parse::<MacAddr6>().unwrap();
+    assert_eq!(
+        batch_items(100, 10, mac("c8:3e:a7:00:00:ff"), 1, 3),
+        Ok(vec![
+            (100, mac("c8:3e:a7:00:00:ff")),
+            (110, mac("c8:3e:a7:00:01:00")),
+            (120, mac("c8:3e:a7:00:01:01")),
+        ])
+    );
+    assert_eq!(
+        batch_items(1, 1, mac("c8:3e:a7:00:00:01"), 4, 3),
+        Ok(vec![
+            (1, mac("c8:3e:a7:00:00:01")),
+            (2, mac("c8:3e:a7:00:00:05")),
+            (3, mac("c8:3e:a7:00:00:09")),
+        ])
+    );
+    assert_eq!(
+        batch_items(1, 1, mac("c8:3e:a7:00:00:01"), 1, 0),
+        Ok(vec![])
+    );
+    assert_eq!(
+        batch_items(u32::MAX - 1, 1, mac("c8:3e:a7:00:00:01"), 1, 3),
+        Err("The serial of image 3 overflows".to_string())
+    );
+    assert_eq!(
+        batch_items(1, 1, mac("ff:ff:ff:ff:ff:fe"), 1, 3),
+        Err("The mac address of image 3 overflows".to_string())
+    );
+}
+
+/// Return the output file name of the image with `serial` in batch mode (see [Cli::batch_count])
+fn batch_outfile(outfile: &Path, serial: u32) -> PathBuf {
+    let name = outfile.to_string_lossy();
+    if name.contains("{serial}") {
+        return PathBuf::from(name.replace("{serial}", &serial.to_string()));
+    }
+    let stem = outfile.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match outfile.extension() {
+        Some(ext) => format!("{stem}-{serial}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{serial}"),
+    };
+    outfile.with_file_name(file_name)
+}
+
+#[test]
+fn test_batch_outfile() {
+    assert_eq!(
+        batch_outfile(Path::new("out.eep"), 4711),
+        PathBuf::from("out-4711.eep")
+    );
+    assert_eq!(
+        batch_outfile(Path::new("images/out"), 4711),
+        PathBuf::from("images/out-4711")
+    );
+    assert_eq!(
+        batch_outfile(Path::new("images/{serial}/eeprom.hex"), 4711),
+        PathBuf::from("images/4711/eeprom.hex")
+    );
+}
+
+/// Create and write the images of the batch mode (see [Cli::batch_count])
+///
+/// All images are created and validated before the first one is written.
+fn write_batch(cli: &Cli, config: &RevPiHatEeprom, count: u32) {
+    let items = match batch_items(
+        cli.start_serial
+            .expect("BUG: The start serial is required in batch mode"),
+        cli.serial_step,
+        cli.start_mac
+            .expect("BUG: The start mac is required in batch mode"),
+        config.mac_count.unwrap_or(1),
+        count,
+    ) {
+        Ok(items) => items,
+        Err(e) => {
+            eprintln!("ERROR: {e}");
+            process::exit(1);
+        }
+    };
+    let mut images = Vec::new();
+    for (serial, mac) in items {
+        let mut config = config.clone();
+        config.serial = Some(serial);
+        config.mac = Some(mac);
+        if let Err(e) = config.validate() {
+            eprintln!("ERROR: Invalid configuration for serial {serial}: {e}");
+            process::exit(1);
+        }
+        check_mac(&config, cli.strict_mac);
+        let eep = match create_rpi_eep(&config, cli.uuid_byte_order) {
+            Ok(eep) => eep,
+            Err(e) => {
+                eprintln!("Error: Can't create EEP for serial {serial}: {e}");
+                process::exit(1);
+            }
+        };
+        let padding = padding_or_exit(eep.len(), cli.size);
+        images.push((serial, mac, eep, padding));
+    }
+
+    let io_timeout = cli.io_timeout_ms.map(Duration::from_millis);
+    for (serial, mac, eep, padding) in images {
+        let crc = eep.image_crc();
+        let outfile = batch_outfile(&cli.outfile_name, serial);
+        prepare_output_dir_or_exit(&outfile, cli.mkdir);
+        if let Err(e) = write_output(eep, outfile.clone(), cli.output_format, padding, io_timeout) {
+            eprintln!(
+                "ERROR: Can't write data to the output file: `{}': {e}",
+                outfile.to_string_lossy()
+            );
+            process::exit(1);
+        }
+        if cli.crc {
+            eprintln!(
+                "{}: serial: {serial}, mac: {mac}, crc16: 0x{crc:04x}",
+                outfile.to_string_lossy()
+            );
+        } else {
+            eprintln!(
+                "{}: serial: {serial}, mac: {mac}",
+                outfile.to_string_lossy()
+            );
+        }
+    }
+}
+
+/// Exit if the mac of the config is invalid (see [revpi_hat_eep::validate_mac()])
+fn check_mac(config: &RevPiHatEeprom, strict: bool) {
+    if let Some(mac) = config.mac {
+        if let Err(e) = revpi_hat_eep::validate_mac(mac, strict) {
+            eprintln!("ERROR: {e}");
+            process::exit(1);
+        }
+        if mac.is_local() {
+            eprintln!("WARNING: {}", revpi_hat_eep::ValidationError::MacLocal(mac));
+        }
+        if let Some(count) = config.mac_count {
+            if let Err(e) = revpi_hat_eep::validate_mac_count(mac, count) {
+                eprintln!("ERROR: {e}");
+                process::exit(1);
+            }
+        }
+    }
+}
+
+fn main() {
+    let mut cli = Cli::parse();
+
+    if cli.version_info {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&version_info())
+                .expect("BUG: Can't serialize the version info")
+        );
+        return;
+    }
+
+    if cli.list_checks {
+        list_checks();
+        return;
+    }
+
+    if cli.schema {
+        print_schema();
+        return;
+    }
+
+    if let Some(path) = &cli.decode {
+        decode_image(path, cli.json_report, cli.uuid_byte_order);
+        return;
+    }
+
+    if let Some(paths) = &cli.compare_images {
+        if compare_image_files(&paths[0], &paths[1], cli.uuid_byte_order) {
+            process::exit(0);
+        }
+        process::exit(1);
+    }
+
+    let config_path = cli
+        .config
+        .clone()
+        .expect("BUG: The CONFIG argument is required at this point");
+
+    let config = match read_config(&config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!(
+                "ERROR: Can't read config file `{}': {e}",
+                config_path.to_string_lossy()
+            );
+            process::exit(1)
+        }
+    };
+
+    let read_gpio_bank = |path: &Option<PathBuf>| {
+        path.as_ref()
+            .map(|path| match std::fs::read_to_string(path) {
+                Ok(bank) => (bank, ConfigFormat::from_path(path)),
+                Err(e) => {
+                    eprintln!(
+                        "ERROR: Can't read gpio bank file `{}': {e}",
+                        path.to_string_lossy()
+                    );
+                    process::exit(1)
+                }
+            })
+    };
+    let gpio_bank0 = read_gpio_bank(&cli.gpio_bank0);
+    let gpio_bank1 = read_gpio_bank(&cli.gpio_bank1);
+
+    let mut config = match revpi_hat_eep::parse_config_files(
+        (&config, ConfigFormat::from_path(&config_path)),
+        gpio_bank0
+            .as_ref()
+            .map(|(bank, format)| (bank.as_str(), *format)),
+        gpio_bank1
+            .as_ref()
+            .map(|(bank, format)| (bank.as_str(), *format)),
+    ) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!(
+                "ERROR: Invalid config file `{}': {e}",
+                config_path.to_string_lossy(),
+            );
+            process::exit(1);
+        }
+    };
+
+    override_product(&cli, &mut config);
+    if let Err(e) = config.validate() {
+        eprintln!("ERROR: Invalid configuration: {e}");
+        process::exit(1);
+    }
+
+    for warning in config.warnings() {
+        eprintln!("WARNING: {warning}");
+    }
+
+    if cli.check {
+        check_mac(&config, cli.strict_mac);
+        println!("OK: {}", config_path.to_string_lossy());
+        return;
+    }
+
+    if let Some(path) = &cli.export_identity {
+        export_identity(&config, path);
+        return;
+    }
+
+    if let Some(other_path) = &cli.gpio_diff {
+        print_gpio_diff(&config, other_path, cli.gpio_diff_format);
+        return;
+    }
+
+    if cli.batch_count.is_some() {
+        // The first serial and mac of the batch take the place of the program arguments
+        cli.serial = cli.start_serial;
+        cli.mac = cli.start_mac;
+    }
+
+    if cli.rebuild {
+        check_full_config(&config, &config_path);
+    } else {
+        resolve_sources(&cli, &mut config, |var| std::env::var(var));
+    }
+
+    check_mac(&config, cli.strict_mac);
+
+    if let Some(count) = cli.batch_count {
+        write_batch(&cli, &config, count);
+        return;
+    }
+
+    if cli.identity {
+        println!(
+            "{}",
+            identity_string(&config, &cli.identity_fields, &cli.identity_delimiter)
+        );
+    }
+
+    if let Some(export_path) = cli.export {
+        export_config(&config, export_path)
+    };
+
+    let eep = match create_rpi_eep(&config, cli.uuid_byte_order) {
+        Ok(eep) => eep,
+        Err(e) => {
+            eprintln!("Error: Can't create EEP: {e}");
+            process::exit(1);
+        }
+    };
+    if cli.count {
+        let expected = expected_atom_count(&config);
+        let count = eep.atoms().len();
+        eprintln!("atoms: {count} (expected: {expected})");
+        if count != expected {
+            eprintln!("WARNING: The image contains {count} atoms, but {expected} were expected");
+        }
+    }
+
+    if cli.explain {
+        for line in explain(&eep) {
+            println!("{line}");
+        }
+    }
+
+    if cli.summary || cli.summary_only {
+        let serial = config
+            .serial
+            .expect("BUG: Missing serial in RevPiHatEeprom configuration");
+        let summary = config
+            .summary(serial, config.edate, config.mac)
+            .with_image_len(eep.len());
+        if cli.summary_only {
+            println!("{summary}");
+            return;
+        }
+        // The summary goes to stderr, so it doesn't interfere with the image or the identity
+        // string on stdout
+        eprintln!("{summary}");
+    }
+
+    let io_timeout = cli.io_timeout_ms.map(Duration::from_millis);
+
+    if let Some(device) = cli.compare_with_device {
+        let mut buf: Vec<u8> = Vec::with_capacity(eep.len());
+        eep.to_bytes(&mut buf);
+        if compare_with_device(&buf, &eep, cli.uuid_byte_order, &device, io_timeout) {
+            process::exit(0);
+        }
+        process::exit(1);
+    }
+
+    let padding = padding_or_exit(eep.len(), cli.size);
+    let crc = eep.image_crc();
+
+    if let Some(device) = cli.write_device {
+        let mut buf: Vec<u8> = Vec::with_capacity(eep.len() + padding);
+        eep.to_bytes(&mut buf);
+        buf.resize(buf.len() + padding, 0xff);
+        match write_device(buf, &device, cli.size, io_timeout) {
+            Ok(written) => {
+                println!(
+                    "Wrote {written} bytes to device `{}'",
+                    device.to_string_lossy()
+                );
+                if cli.crc {
+                    eprintln!("crc16: 0x{crc:04x}");
+                }
+            }
+            Err(e) => {
+                eprintln!(
+                    "ERROR: Can't write device `{}': {e}",
+                    device.to_string_lossy()
+                );
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    prepare_output_dir_or_exit(&cli.outfile_name, cli.mkdir);
+    let result = write_output(
+        eep,
+        cli.outfile_name.clone(),
+        cli.output_format,
+        padding,
+        io_timeout,
+    );
+    if let Err(e) = result {
+        eprintln!(
+            "ERROR: Can't write data to the output file: `{}': {e}",
+            cli.outfile_name.to_string_lossy()
+        );
+        process::exit(1);
+    }
+    if cli.crc {
+        eprintln!("crc16: 0x{crc:04x}");
+    }
+}
+
+/// Write the image in the `format` padded with `padding` bytes to the output file
+fn write_output(
+    eep: Eep,
+    outfile_name: PathBuf,
+    format: OutputFormat,
+    padding: usize,
+    timeout: Option<Duration>,
+) -> io::Result<()> {
+    with_timeout(timeout, move || {
+        let mut writer: BufWriter<Box<dyn Write>> = if outfile_name == Path::new("-") {
+            BufWriter::new(Box::new(io::stdout()))
+        } else {
+            let output_file = OpenOptions::new()
+                .read(false)
+                .write(true)
+                .truncate(true)
+                .create(true)
+                .open(outfile_name)?;
+            BufWriter::new(Box::new(output_file))
+        };
+        let padded = |eep: &Eep| {
+            let mut buf: Vec<u8> = Vec::with_capacity(eep.len() + padding);
+            eep.to_bytes(&mut buf);
+            buf.resize(buf.len() + padding, 0xff);
+            buf
+        };
+        match format {
+            OutputFormat::Bin => {
+                eep.to_writer(&mut writer)?;
+                writer.write_all(&vec![0xff; padding])?;
+            }
+            OutputFormat::Ihex => writer.write_all(to_ihex(&padded(&eep)).as_bytes())?,
+            OutputFormat::Carray => writer.write_all(to_c_array(&padded(&eep)).as_bytes())?,
+        }
+        writer.flush()
+    })
+}
@@ -18,6 +18,7 @@ const MAX_GPIOS: usize = BANK0_GPIOS + BANK1_GPIOS;
 /// depends not on this configuration.
 ///
 /// For details see: [RevPi HAT EEPROM Format: GPIO map atom data](https://github.com/RevolutionPi/revpi-hat-eeprom/blob/master/docs/RevPi-HAT-EEPROM-Format.md#gpio-map-atom-data-type0x0002)
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 #[serde(rename_all = "snake_case")]
 pub enum GpioBankDrive {
@@ -80,9 +81,11 @@ impl From<GpioBankDrive> for gpio_map::GpioDrive {
 /// depends not on this configuration.
 ///
 /// For details see: [RevPi HAT EEPROM Format: GPIO map atom data](https://github.com/RevolutionPi/revpi-hat-eeprom/blob/master/docs/RevPi-HAT-EEPROM-Format.md#gpio-map-atom-data-type0x0002)
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
 #[serde(rename_all = "snake_case")]
 pub enum GpioBankSlew {
+    #[default]
     Default,
     RateLimiting,
     NoLimit,
@@ -116,9 +119,11 @@ impl From<GpioBankSlew> for gpio_map::GpioSlew {
 /// hysteresis depends not on this configuration.
 ///
 /// For details see: [RevPi HAT EEPROM Format: GPIO map atom data](https://github.com/RevolutionPi/revpi-hat-eeprom/blob/master/docs/RevPi-HAT-EEPROM-Format.md#gpio-map-atom-data-type0x0002)
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
 #[serde(rename_all = "snake_case")]
 pub enum GpioBankHysteresis {
+    #[default]
     Default,
     Disable,
     Enable,
@@ -144,7 +149,47 @@ impl From<GpioBankHysteresis> for gpio_map::GpioHysteresis {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+/// This defines possible values for the back power of the board
+///
+/// The board can either not back power the Pi or back power it with up to 1.3A or 2A. With `2A`
+/// the high current USB mode is automatically enabled. The reserved value of the GPIO map atom can't
+/// be configured.
+///
+/// For details see: [RevPi HAT EEPROM Format: GPIO map atom data](https://github.com/RevolutionPi/revpi-hat-eeprom/blob/master/docs/RevPi-HAT-EEPROM-Format.md#gpio-map-atom-data-type0x0002)
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GpioBankBackPower {
+    #[default]
+    None,
+    #[serde(rename = "1A3")]
+    BackPower1A3,
+    #[serde(rename = "2A")]
+    BackPower2A,
+}
+
+impl From<gpio_map::GpioBackPower> for GpioBankBackPower {
+    fn from(back_power: gpio_map::GpioBackPower) -> Self {
+        match back_power {
+            gpio_map::GpioBackPower::None => GpioBankBackPower::None,
+            gpio_map::GpioBackPower::BackPower1A3 => GpioBankBackPower::BackPower1A3,
+            gpio_map::GpioBackPower::BackPower2A => GpioBankBackPower::BackPower2A,
+        }
+    }
+}
+
+impl From<GpioBankBackPower> for gpio_map::GpioBackPower {
+    fn from(back_power: GpioBankBackPower) -> Self {
+        match back_power {
+            GpioBankBackPower::None => gpio_map::GpioBackPower::None,
+            GpioBankBackPower::BackPower1A3 => gpio_map::GpioBackPower::BackPower1A3,
+            GpioBankBackPower::BackPower2A => gpio_map::GpioBackPower::BackPower2A,
+        }
+    }
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum GpioFsel {
     Input,
@@ -157,6 +202,22 @@ pub enum GpioFsel {
     Alt5,
 }
 
+impl Display for GpioFsel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fsel = match self {
+            GpioFsel::Input => "input",
+            GpioFsel::Output => "output",
+            GpioFsel::Alt0 => "alt0",
+            GpioFsel::Alt1 => "alt1",
+            GpioFsel::Alt2 => "alt2",
+            GpioFsel::Alt3 => "alt3",
+            GpioFsel::Alt4 => "alt4",
+            GpioFsel::Alt5 => "alt5",
+        };
+        write!(f, "{}", fsel)
+    }
+}
+
 impl From<gpio_map::GpioFsel> for GpioFsel {
     fn from(fsel: gpio_map::GpioFsel) -> Self {
         match fsel {
@@ -187,15 +248,29 @@ impl From<GpioFsel> for gpio_map::GpioFsel {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum GpioPull {
+    #[default]
     Default,
     Up,
     Down,
     None,
 }
 
+impl Display for GpioPull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pull = match self {
+            GpioPull::Default => "default",
+            GpioPull::Up => "up",
+            GpioPull::Down => "down",
+            GpioPull::None => "none",
+        };
+        write!(f, "{}", pull)
+    }
+}
+
 impl From<gpio_map::GpioPull> for GpioPull {
     fn from(pull: gpio_map::GpioPull) -> Self {
         match pull {
@@ -227,77 +302,240 @@ impl From<GpioPull> for gpio_map::GpioPull {
 /// For the alternate pin functions see the reference manual of your SoC.
 /// The pull confirguation can be set to [pullup](GpioPull::Up),
 /// [pulldown](GpioPull::Down), [no pull](GpioPull::None) and to leave it at
+/// [default](GpioPull::Default). If the pull configuration is omitted it is left at
 /// [default](GpioPull::Default).
 ///
+/// A pin is marked as used by the board unless `used` is set to `false`, which declares a pin that
+/// is present but not connected.
+///
 /// Currently only the first gpio bank is supported by the HAT EEPROM. Thus
 /// leavs only the first 28 gpios. The gpios 0 and 1 are used for the HAT EEPROM
 /// and should not be changed. The gpio bank validation will not allow to modify
 /// the gpios 0 and 1 also the gpios higher then 27.
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct GpioPin {
     gpio: u8,
     fsel: GpioFsel,
+    #[serde(default)]
     pull: GpioPull,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    used: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_comment",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<GpioComment>"))]
     comment: Option<Vec<String>>,
 }
 
+impl GpioPin {
+    /// The gpio number
+    pub fn gpio(&self) -> u8 {
+        self.gpio
+    }
+
+    /// The function select
+    pub fn fsel(&self) -> GpioFsel {
+        self.fsel
+    }
+
+    /// The pull resistor setting
+    pub fn pull(&self) -> GpioPull {
+        self.pull
+    }
+
+    /// Whether the board uses the pin (default: `true`)
+    pub fn used(&self) -> bool {
+        self.used.unwrap_or(true)
+    }
+
+    /// The comment lines (not part of the image)
+    pub fn comment(&self) -> Option<&[String]> {
+        self.comment.as_deref()
+    }
+}
+
+/// A gpio comment can be a single string or a list of strings (lines)
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GpioComment {
+    Line(String),
+    Lines(Vec<String>),
+}
+
+fn deserialize_comment<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(
+        Option::<GpioComment>::deserialize(deserializer)?.map(|comment| match comment {
+            GpioComment::Line(line) => vec![line],
+            GpioComment::Lines(lines) => lines,
+        }),
+    )
+}
+
+#[test]
+fn test_gpio_pin_comment() {
+    let pin = |comment: &str| {
+        serde_json::from_str::<GpioPin>(&format!(r#"{{ "gpio": 2, "fsel": "input" {comment} }}"#))
+    };
+    assert_eq!(pin("").unwrap().comment, None);
+    assert_eq!(
+        pin(r#", "comment": "single note""#).unwrap().comment,
+        Some(vec!["single note".to_string()])
+    );
+    assert_eq!(
+        pin(r#", "comment": ["a", "b"]"#).unwrap().comment,
+        Some(vec!["a".to_string(), "b".to_string()])
+    );
+    assert!(pin(r#", "comment": 1"#).is_err());
+
+    // The comment is always serialized as a list
+    let json = serde_json::to_string(&pin(r#", "comment": "single note""#).unwrap()).unwrap();
+    assert!(json.contains(r#""comment":["single note"]"#));
+    let json = serde_json::to_string(&pin("").unwrap()).unwrap();
+    assert!(!json.contains("comment"));
+}
+
+#[test]
+fn test_gpio_bank_comment() {
+    let bank = |comment: &str| {
+        serde_json::from_str::<GpioBank>(&format!(
+            r#"{{ "drive": "default", "gpios": [ {{ "gpio": 2, "fsel": "input", "comment": {comment} }} ] }}"#
+        ))
+        .unwrap()
+    };
+    assert!(bank(r#""note""#)
+        .validate(gpio_map::GpioBank::Bank0)
+        .is_ok());
+    for comment in [r#""""#, "[]", r#"["note", ""]"#, r#"" ""#] {
+        assert_eq!(
+            bank(comment).validate(gpio_map::GpioBank::Bank0),
+            Err(ValidationError::GpioCommentEmpty(2))
+        );
+    }
+
+    // The comments are kept by an export of the configuration
+    let json = serde_json::to_string(&bank(r#"["a", "b"]"#)).unwrap();
+    let bank: GpioBank = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        bank.gpios[0].comment,
+        Some(vec!["a".to_string(), "b".to_string()])
+    );
+}
+
 /// This struct represents the GPIO configuration of the HAT EEPROM
 ///
 /// This struct is used to deserialize the GPIO configuration from a RevPi HAT
 /// EEPROM configuration in json format. See [RevPi HAT EEPROM Format: GPIO map
 /// atom data](https://github.com/RevolutionPi/revpi-hat-eeprom/blob/master/docs/RevPi-HAT-EEPROM-Format.md#gpio-map-atom-data-type0x0002)
-/// for details about the meaning of the values in this struct.
+/// for details about the meaning of the values in this struct. The slew rate and
+/// the hysteresis are optional and left at default if omitted. The back power is
+/// optional and set to [none](GpioBankBackPower::None) if omitted.
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct GpioBank {
     drive: GpioBankDrive,
+    #[serde(default)]
     slew: GpioBankSlew,
+    #[serde(default)]
     hysteresis: GpioBankHysteresis,
+    #[serde(default)]
+    back_power: GpioBankBackPower,
     gpios: Vec<GpioPin>,
 }
 
 impl GpioBank {
+    /// The drive strength of the bank
+    pub fn drive(&self) -> GpioBankDrive {
+        self.drive
+    }
+
+    /// The slew rate of the bank
+    pub fn slew(&self) -> GpioBankSlew {
+        self.slew
+    }
+
+    /// The hysteresis of the bank
+    pub fn hysteresis(&self) -> GpioBankHysteresis {
+        self.hysteresis
+    }
+
+    /// The back power of the board
+    pub fn back_power(&self) -> GpioBankBackPower {
+        self.back_power
+    }
+
+    /// The configured gpios
+    pub fn gpios(&self) -> &[GpioPin] {
+        &self.gpios
+    }
+
+    /// Return the numbers of all configured gpios
+    pub(crate) fn gpio_numbers(&self) -> impl Iterator<Item = u8> + '_ {
+        self.gpios.iter().map(|gpio| gpio.gpio)
+    }
+
     pub fn validate(&self, bank_no: gpio_map::GpioBank) -> Result<(), ValidationError> {
+        if bank_no == gpio_map::GpioBank::Bank1 && self.back_power != GpioBankBackPower::None {
+            return Err(ValidationError::BackPowerBank1(self.back_power));
+        }
         let mut configured_gpios: Vec<bool> = vec![false; MAX_GPIOS];
         for gpio in &self.gpios {
             if gpio.gpio == 0 || gpio.gpio == 1 {
-                return Err(ValidationError(format!(
-                    "gpio# mustn't be 0 or 1 (they are used for the HAT EEPROM): {}",
-                    gpio.gpio
-                )));
+                return Err(ValidationError::GpioHatEeprom(gpio.gpio));
             }
             match bank_no {
                 gpio_map::GpioBank::Bank0 => {
                     if gpio.gpio as usize >= BANK0_GPIOS {
-                        return Err(ValidationError(format!(
-                            "gpio# (bank0): {} (MIN: {}, MAX: {})",
-                            gpio.gpio, 2, BANK0_GPIOS - 1
-                        )));
+                        return Err(ValidationError::GpioOutOfRange {
+                            bank: bank_no,
+                            gpio: gpio.gpio,
+                        });
                     }
                 }
                 gpio_map::GpioBank::Bank1 => {
                     if (gpio.gpio as usize) >= BANK0_GPIOS + BANK1_GPIOS
                         || (gpio.gpio as usize) < BANK0_GPIOS
                     {
-                        return Err(ValidationError(format!(
-                            "gpio# (bank1): {} (MIN: {}, MAX: {})",
-                            gpio.gpio, BANK0_GPIOS, MAX_GPIOS - 1
-                        )));
+                        return Err(ValidationError::GpioOutOfRange {
+                            bank: bank_no,
+                            gpio: gpio.gpio,
+                        });
                     }
                 }
             }
             if configured_gpios[gpio.gpio as usize] {
-                return Err(ValidationError(format!(
-                    "gpio#: {} defined more then once",
-                    gpio.gpio
-                )));
+                return Err(ValidationError::GpioDuplicate(gpio.gpio));
             }
             configured_gpios[gpio.gpio as usize] = true;
+            if let Some(comment) = &gpio.comment {
+                if comment.is_empty() || comment.iter().any(|line| line.trim().is_empty()) {
+                    return Err(ValidationError::GpioCommentEmpty(gpio.gpio));
+                }
+            }
         }
         Ok(())
     }
+
+    /// Return the violations of the warning rules of the gpio bank
+    ///
+    /// A pin marked as unused with a non-default function or pull is usually a mistake.
+    pub fn warnings(&self) -> Vec<ValidationError> {
+        self.gpios
+            .iter()
+            .filter(|gpio| {
+                !gpio.used() && (gpio.fsel != GpioFsel::Input || gpio.pull != GpioPull::Default)
+            })
+            .map(|gpio| ValidationError::GpioUnusedConfigured(gpio.gpio))
+            .collect()
+    }
 }
 
 impl Display for GpioBank {
@@ -307,8 +545,9 @@ impl Display for GpioBank {
 }
 
 impl GpioBank {
-    pub fn into_gpio_map(
-        self,
+    /// Convert the gpio bank configuration into a GPIO map atom without consuming it
+    pub fn to_gpio_map(
+        &self,
         bank: gpio_map::GpioBank,
     ) -> Result<gpio_map::EepAtomGpioMapData, gpio_map::GpioError> {
         let mut gpio_map = gpio_map::EepAtomGpioMapData::new(
@@ -316,15 +555,434 @@ impl GpioBank {
             self.drive.into(),
             self.slew.into(),
             self.hysteresis.into(),
-            gpio_map::GpioBackPower::None,
+            self.back_power.into(),
         );
 
-        for gpio in self.gpios {
+        for gpio in &self.gpios {
             gpio_map.set(
                 gpio.gpio as usize,
-                gpio_map::GpioPin::new(gpio.fsel.into(), gpio.pull.into(), true),
+                gpio_map::GpioPin::new(gpio.fsel.into(), gpio.pull.into(), gpio.used()),
             )?;
         }
         Ok(gpio_map)
     }
+
+    /// Convert the gpio bank configuration into a GPIO map atom
+    ///
+    /// This is the same as [GpioBank::to_gpio_map()], but consumes the gpio bank.
+    pub fn into_gpio_map(
+        self,
+        bank: gpio_map::GpioBank,
+    ) -> Result<gpio_map::EepAtomGpioMapData, gpio_map::GpioError> {
+        self.to_gpio_map(bank)
+    }
+
+    /// Create a gpio bank configuration from a GPIO map atom
+    ///
+    /// This is the reverse of [GpioBank::to_gpio_map()]. The used pins and the unused pins with a
+    /// non-default function or pull are added to the configuration; comments are lost.
+    pub fn from_gpio_map(gpio_map: &gpio_map::EepAtomGpioMapData) -> GpioBank {
+        let offset = match gpio_map.bank() {
+            gpio_map::GpioBank::Bank0 => 0,
+            gpio_map::GpioBank::Bank1 => BANK0_GPIOS,
+        };
+        let gpios = gpio_map
+            .gpios()
+            .iter()
+            .enumerate()
+            .filter(|(_, pin)| {
+                pin.used()
+                    || pin.fsel() != gpio_map::GpioFsel::Input
+                    || pin.pull() != gpio_map::GpioPull::Default
+            })
+            .map(|(n, pin)| GpioPin {
+                gpio: (n + offset) as u8,
+                fsel: pin.fsel().into(),
+                pull: pin.pull().into(),
+                used: if pin.used() { None } else { Some(false) },
+                comment: None,
+            })
+            .collect();
+        GpioBank {
+            drive: gpio_map.drive().into(),
+            slew: gpio_map.slew().into(),
+            hysteresis: gpio_map.hysteresis().into(),
+            back_power: gpio_map.back_power().into(),
+            gpios,
+        }
+    }
+}
+
+#[test]
+fn test_gpio_bank_defaults() {
+    let bank: GpioBank = serde_json::from_str(
+        r#"{
+            "drive": "8mA",
+            "gpios": [
+                { "gpio": 2, "fsel": "output" },
+                { "gpio": 3, "fsel": "alt0", "pull": "up" }
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert!(matches!(bank.slew, GpioBankSlew::Default));
+    assert!(matches!(bank.hysteresis, GpioBankHysteresis::Default));
+    assert!(matches!(bank.gpios[0].pull, GpioPull::Default));
+    assert!(matches!(bank.gpios[1].pull, GpioPull::Up));
+
+    // The getters return the parsed values
+    assert!(matches!(bank.drive(), GpioBankDrive::Drive8mA));
+    assert_eq!(bank.back_power(), GpioBankBackPower::None);
+    assert_eq!(bank.gpios().len(), 2);
+    assert_eq!(bank.gpios()[1].gpio(), 3);
+    assert_eq!(bank.gpios()[1].fsel(), GpioFsel::Alt0);
+    assert_eq!(bank.gpios()[1].pull(), GpioPull::Up);
+    assert_eq!(bank.gpios()[1].comment(), None);
+
+    // The mandatory fields can't be omitted
+    assert!(serde_json::from_str::<GpioBank>(r#"{ "gpios": [] }"#).is_err());
+    assert!(
+        serde_json::from_str::<GpioBank>(r#"{ "drive": "8mA", "gpios": [ { "gpio": 2 } ] }"#)
+            .is_err()
+    );
+
+    // Unknown fields are still rejected
+    assert!(serde_json::from_str::<GpioBank>(
+        r#"{ "drive": "8mA", "slews": "default", "gpios": [] }"#
+    )
+    .is_err());
+    assert!(serde_json::from_str::<GpioBank>(
+        r#"{ "drive": "8mA", "gpios": [ { "gpio": 2, "fsel": "input", "pul": "up" } ] }"#
+    )
+    .is_err());
+}
+
+#[test]
+fn test_gpio_bank_back_power() {
+    let bank = |back_power: &str| {
+        serde_json::from_str::<GpioBank>(&format!(
+            r#"{{ "drive": "default", {back_power} "gpios": [] }}"#
+        ))
+    };
+    assert_eq!(bank("").unwrap().back_power, GpioBankBackPower::None);
+    for (json, expected) in [
+        ("none", gpio_map::GpioBackPower::None),
+        ("1A3", gpio_map::GpioBackPower::BackPower1A3),
+        ("2A", gpio_map::GpioBackPower::BackPower2A),
+    ] {
+        let bank = bank(&format!(r#""back_power": "{json}","#)).unwrap();
+        let gpio_map = bank.to_gpio_map(gpio_map::GpioBank::Bank0).unwrap();
+        assert_eq!(gpio_map.back_power(), expected);
+        assert_eq!(
+            GpioBank::from_gpio_map(&gpio_map).back_power,
+            bank.back_power
+        );
+    }
+    // The reserved value can't be configured
+    assert!(bank(r#""back_power": 3,"#).is_err());
+    assert!(bank(r#""back_power": "reserved","#).is_err());
+
+    // The back power can only be defined for bank 0
+    for back_power in ["none", "1A3", "2A"] {
+        let bank = bank(&format!(r#""back_power": "{back_power}","#)).unwrap();
+        assert!(bank.validate(gpio_map::GpioBank::Bank0).is_ok());
+        match bank.validate(gpio_map::GpioBank::Bank1) {
+            Ok(()) => assert_eq!(back_power, "none"),
+            Err(e) => assert_eq!(e, ValidationError::BackPowerBank1(bank.back_power)),
+        }
+    }
+}
+
+/// A difference of a single gpio between two configurations (see [gpio_diff()])
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum GpioDiff {
+    /// The gpio is only configured in the new configuration
+    Added {
+        gpio: u8,
+        fsel: GpioFsel,
+        pull: GpioPull,
+        used: bool,
+    },
+    /// The gpio is only configured in the old configuration
+    Removed {
+        gpio: u8,
+        fsel: GpioFsel,
+        pull: GpioPull,
+        used: bool,
+    },
+    /// The fsel, pull and/or used flag of the gpio changed
+    Changed {
+        gpio: u8,
+        old_fsel: GpioFsel,
+        old_pull: GpioPull,
+        old_used: bool,
+        new_fsel: GpioFsel,
+        new_pull: GpioPull,
+        new_used: bool,
+    },
+}
+
+impl Display for GpioDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpioDiff::Added {
+                gpio,
+                fsel,
+                pull,
+                used,
+            } => {
+                write!(f, "+ gpio {gpio}: fsel: {fsel}, pull: {pull}")?;
+                if !used {
+                    write!(f, ", unused")?;
+                }
+                Ok(())
+            }
+            GpioDiff::Removed {
+                gpio,
+                fsel,
+                pull,
+                used,
+            } => {
+                write!(f, "- gpio {gpio}: fsel: {fsel}, pull: {pull}")?;
+                if !used {
+                    write!(f, ", unused")?;
+                }
+                Ok(())
+            }
+            GpioDiff::Changed {
+                gpio,
+                old_fsel,
+                old_pull,
+                old_used,
+                new_fsel,
+                new_pull,
+                new_used,
+            } => {
+                write!(
+                    f,
+                    "~ gpio {gpio}: fsel: {old_fsel} -> {new_fsel}, pull: {old_pull} -> {new_pull}"
+                )?;
+                if old_used != new_used {
+                    write!(f, ", used: {old_used} -> {new_used}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Compare the gpios of two configurations
+///
+/// The gpios of all banks are compared by their number. Only the fsel, pull and used flag are
+/// compared, the comments are ignored. The differences are sorted by the gpio number.
+pub fn gpio_diff(old: &[GpioBank], new: &[GpioBank]) -> Vec<GpioDiff> {
+    let mut old_gpios: Vec<Option<&GpioPin>> = vec![None; MAX_GPIOS];
+    for gpio in old.iter().flat_map(|bank| &bank.gpios) {
+        if let Some(slot) = old_gpios.get_mut(gpio.gpio as usize) {
+            *slot = Some(gpio);
+        }
+    }
+    let mut new_gpios: Vec<Option<&GpioPin>> = vec![None; MAX_GPIOS];
+    for gpio in new.iter().flat_map(|bank| &bank.gpios) {
+        if let Some(slot) = new_gpios.get_mut(gpio.gpio as usize) {
+            *slot = Some(gpio);
+        }
+    }
+
+    old_gpios
+        .into_iter()
+        .zip(new_gpios)
+        .filter_map(|pins| match pins {
+            (None, None) => None,
+            (None, Some(new)) => Some(GpioDiff::Added {
+                gpio: new.gpio,
+                fsel: new.fsel,
+                pull: new.pull,
+                used: new.used(),
+            }),
+            (Some(old), None) => Some(GpioDiff::Removed {
+                gpio: old.gpio,
+                fsel: old.fsel,
+                pull: old.pull,
+                used: old.used(),
+            }),
+            (Some(old), Some(new)) => {
+                if old.fsel == new.fsel && old.pull == new.pull && old.used() == new.used() {
+                    None
+                } else {
+                    Some(GpioDiff::Changed {
+                        gpio: old.gpio,
+                        old_fsel: old.fsel,
+                        old_pull: old.pull,
+                        old_used: old.used(),
+                        new_fsel: new.fsel,
+                        new_pull: new.pull,
+                        new_used: new.used(),
+                    })
+                }
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_gpio_diff() {
+    let old: GpioBank = serde_json::from_str(
+        r#"{ "drive": "default", "gpios": [
+            { "gpio": 2, "fsel": "input" },
+            { "gpio": 3, "fsel": "output", "pull": "none" },
+            { "gpio": 4, "fsel": "alt1", "pull": "up", "comment": [ "I2C" ] },
+            { "gpio": 6, "fsel": "input" }
+        ] }"#,
+    )
+    .unwrap();
+    let new: GpioBank = serde_json::from_str(
+        r#"{ "drive": "8mA", "gpios": [
+            { "gpio": 4, "fsel": "alt1", "pull": "up" },
+            { "gpio": 3, "fsel": "output", "pull": "down" },
+            { "gpio": 5, "fsel": "output" },
+            { "gpio": 6, "fsel": "input", "used": false },
+            { "gpio": 7, "fsel": "input", "used": false }
+        ] }"#,
+    )
+    .unwrap();
+    let bank1: GpioBank = serde_json::from_str(
+        r#"{ "drive": "default", "gpios": [ { "gpio": 31, "fsel": "input" } ] }"#,
+    )
+    .unwrap();
+
+    assert!(gpio_diff(std::slice::from_ref(&old), std::slice::from_ref(&old)).is_empty());
+    let diff = gpio_diff(&[old], &[new, bank1]);
+    assert_eq!(
+        diff,
+        vec![
+            GpioDiff::Removed {
+                gpio: 2,
+                fsel: GpioFsel::Input,
+                pull: GpioPull::Default,
+                used: true
+            },
+            GpioDiff::Changed {
+                gpio: 3,
+                old_fsel: GpioFsel::Output,
+                old_pull: GpioPull::None,
+                old_used: true,
+                new_fsel: GpioFsel::Output,
+                new_pull: GpioPull::Down,
+                new_used: true
+            },
+            GpioDiff::Added {
+                gpio: 5,
+                fsel: GpioFsel::Output,
+                pull: GpioPull::Default,
+                used: true
+            },
+            GpioDiff::Changed {
+                gpio: 6,
+                old_fsel: GpioFsel::Input,
+                old_pull: GpioPull::Default,
+                old_used: true,
+                new_fsel: GpioFsel::Input,
+                new_pull: GpioPull::Default,
+                new_used: false
+            },
+            GpioDiff::Added {
+                gpio: 7,
+                fsel: GpioFsel::Input,
+                pull: GpioPull::Default,
+                used: false
+            },
+            GpioDiff::Added {
+                gpio: 31,
+                fsel: GpioFsel::Input,
+                pull: GpioPull::Default,
+                used: true
+            },
+        ]
+    );
+    assert_eq!(
+        diff[1].to_string(),
+        "~ gpio 3: fsel: output -> output, pull: none -> down"
+    );
+    assert_eq!(
+        serde_json::to_string(&diff[0]).unwrap(),
+        r#"{"change":"removed","gpio":2,"fsel":"input","pull":"default","used":true}"#
+    );
+    assert_eq!(
+        diff[3].to_string(),
+        "~ gpio 6: fsel: input -> input, pull: default -> default, used: true -> false"
+    );
+    assert_eq!(
+        diff[4].to_string(),
+        "+ gpio 7: fsel: input, pull: default, unused"
+    );
+}
+
+#[test]
+fn test_gpio_pin_used() {
+    let bank: GpioBank = serde_json::from_str(
+        r#"{
+            "drive": "default",
+            "gpios": [
+                { "gpio": 2, "fsel": "output" },
+                { "gpio": 3, "fsel": "input", "used": false },
+                { "gpio": 4, "fsel": "alt0", "used": true },
+                { "gpio": 5, "fsel": "output", "pull": "up", "used": false }
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert!(bank.gpios[0].used());
+    assert!(!bank.gpios[1].used());
+    assert!(bank.gpios[2].used());
+
+    let gpio_map = bank.to_gpio_map(gpio_map::GpioBank::Bank0).unwrap();
+    let used: Vec<bool> = gpio_map.gpios()[2..6]
+        .iter()
+        .map(|pin| pin.used())
+        .collect();
+    assert_eq!(used, [true, false, true, false]);
+
+    // The unused pin with a default configuration can't be told from an unconfigured pin
+    let decoded = GpioBank::from_gpio_map(&gpio_map);
+    let gpios: Vec<(u8, bool)> = decoded
+        .gpios()
+        .iter()
+        .map(|gpio| (gpio.gpio(), gpio.used()))
+        .collect();
+    assert_eq!(gpios, [(2, true), (4, true), (5, false)]);
+    assert_eq!(
+        decoded.to_gpio_map(gpio_map::GpioBank::Bank0).unwrap(),
+        gpio_map
+    );
+    // `used` is only serialized if it is `false`
+    let json = serde_json::to_string(&decoded).unwrap();
+    assert_eq!(json.matches(r#""used""#).count(), 1);
+}
+
+#[test]
+fn test_gpio_bank_warnings() {
+    let bank = |gpio: &str| {
+        serde_json::from_str::<GpioBank>(&format!(
+            r#"{{ "drive": "default", "gpios": [ {gpio} ] }}"#
+        ))
+        .unwrap()
+    };
+    for gpio in [
+        r#"{ "gpio": 5, "fsel": "output" }"#,
+        r#"{ "gpio": 5, "fsel": "output", "used": true }"#,
+        r#"{ "gpio": 5, "fsel": "input", "used": false }"#,
+    ] {
+        assert!(bank(gpio).warnings().is_empty());
+    }
+    for gpio in [
+        r#"{ "gpio": 5, "fsel": "output", "used": false }"#,
+        r#"{ "gpio": 5, "fsel": "input", "pull": "down", "used": false }"#,
+    ] {
+        let bank = bank(gpio);
+        assert_eq!(bank.warnings(), [ValidationError::GpioUnusedConfigured(5)]);
+        // It is only a warning
+        assert!(bank.validate(gpio_map::GpioBank::Bank0).is_ok());
+    }
 }
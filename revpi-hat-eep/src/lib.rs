@@ -7,17 +7,465 @@ pub mod gpio;
 use self::gpio::GpioBank;
 use chrono::NaiveDate;
 use macaddr::MacAddr6;
-use rpi_hat_eep::gpio_map;
+use rpi_hat_eep::{
+    gpio_map, Eep, EepAtom, EepAtomCustomData, EepAtomData, EepAtomLinuxDTBData, EepAtomVendorData,
+    LinuxDTB, UuidByteOrder,
+};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
-pub struct ValidationError(String);
+/// A violation of one of the [validation_rules()]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The format `version` is not supported
+    UnsupportedFormatVersion(u16),
+    /// The `eeprom_data_version` is not in the range of [EEPROM_DATA_VERSION_MIN] -
+    /// [EEPROM_DATA_VERSION_MAX]
+    InvalidEepromDataVersion(u16),
+    /// The product version `pver` is not in the range of [PVER_MIN] - [PVER_MAX]
+    InvalidPver(u16),
+    /// The product string `pstr` is too long
+    PstrTooLong(String),
+    /// The vendor string `vstr` is too long
+    VstrTooLong(String),
+    /// The device tree string `dtstr` is too long
+    DtstrTooLong(String),
+    /// The device tree string `dtstr` is empty
+    DtstrEmpty,
+    /// The device tree string `dtstr` contains a character other than `A-Z`, `a-z`, `0-9`, `.`,
+    /// `_` or `-`
+    DtstrInvalidChar { dtstr: String, c: char },
+    /// The mac address is all zeros
+    MacNil,
+    /// The mac address is the broadcast address
+    MacBroadcast,
+    /// The mac address is a multicast address
+    MacMulticast(MacAddr6),
+    /// The mac address is locally administered (only rejected by [validate_mac()] in strict mode)
+    MacLocal(MacAddr6),
+    /// The `mac_count` is 0
+    MacCountZero,
+    /// The last mac address of the block of `count` addresses overflows
+    MacCountOverflow { mac: MacAddr6, count: u8 },
+    /// There are not one or two gpio banks
+    GpioBankCount(usize),
+    /// A gpio bank1 is defined without a gpio bank0
+    GpioBank1WithoutBank0,
+    /// A `back_power` other than `none` is defined for gpio bank1
+    BackPowerBank1(gpio::GpioBankBackPower),
+    /// The gpio 0 or 1 is configured, they are used for the HAT EEPROM
+    GpioHatEeprom(u8),
+    /// The gpio is not in the range of the gpio bank
+    GpioOutOfRange { bank: gpio_map::GpioBank, gpio: u8 },
+    /// The gpio is configured more than once in a gpio bank
+    GpioDuplicate(u8),
+    /// The gpio is configured in gpio bank0 and gpio bank1
+    GpioInBothBanks(u8),
+    /// The `comment` of the gpio is an empty list or contains an empty line
+    GpioCommentEmpty(u8),
+    /// The gpio is marked as unused, but has a non-default `fsel` or `pull` (a warning)
+    GpioUnusedConfigured(u8),
+}
 
 impl std::error::Error for ValidationError {}
 
 impl std::fmt::Display for ValidationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        match self {
+            ValidationError::UnsupportedFormatVersion(version) => {
+                write!(f, "invalid value: `{version}`: Unsupported format version")
+            }
+            ValidationError::InvalidEepromDataVersion(version) => write!(
+                f,
+                "invalid value: `{version}`: The eeprom_data_version is the version of the \
+                EEPROM content of the product; it starts with 1 and is incremented with every \
+                released change"
+            ),
+            ValidationError::InvalidPver(pver) => write!(
+                f,
+                "invalid value: `{pver}`: Product version {}.{:02} (pver / 100) must be in the \
+                range {}.{:02} - {}.{:02}",
+                pver / 100,
+                pver % 100,
+                PVER_MIN / 100,
+                PVER_MIN % 100,
+                PVER_MAX / 100,
+                PVER_MAX % 100
+            ),
+            ValidationError::PstrTooLong(pstr) => write!(
+                f,
+                "invalid value: `{pstr}`: Product string to long {} (max: {}) bytes",
+                pstr.len(),
+                u8::MAX
+            ),
+            ValidationError::VstrTooLong(vstr) => write!(
+                f,
+                "invalid value: `{vstr}`: Vendor string to long: {} (max: {}) bytes",
+                vstr.len(),
+                u8::MAX
+            ),
+            ValidationError::DtstrTooLong(dtstr) => write!(
+                f,
+                "invalid value: `{dtstr}`: Device tree string to long: {} (max: {}) bytes",
+                dtstr.len(),
+                u32::MAX
+            ),
+            ValidationError::DtstrEmpty => write!(f, "invalid value: ``: Empty device tree string"),
+            ValidationError::DtstrInvalidChar { dtstr, c } => write!(
+                f,
+                "invalid value: `{dtstr}`: Invalid character in device tree string: {c:?} \
+                (allowed: A-Z, a-z, 0-9, `.`, `_` and `-`)"
+            ),
+            ValidationError::MacNil => write!(f, "invalid mac address: all zeros"),
+            ValidationError::MacBroadcast => write!(f, "invalid mac address: broadcast address"),
+            ValidationError::MacMulticast(mac) => {
+                write!(f, "invalid mac address: `{mac}`: multicast address")
+            }
+            ValidationError::MacLocal(mac) => {
+                write!(
+                    f,
+                    "invalid mac address: `{mac}`: locally administered address"
+                )
+            }
+            ValidationError::MacCountZero => {
+                write!(f, "invalid value: `0`: mac_count must be at least 1")
+            }
+            ValidationError::MacCountOverflow { mac, count } => write!(
+                f,
+                "invalid mac_count: `{count}`: The block of {count} mac addresses starting at \
+                `{mac}` contains an invalid address"
+            ),
+            ValidationError::GpioBankCount(count) => write!(
+                f,
+                "unsupported number of gpio banks: {count} (min: 1; max: 2)"
+            ),
+            ValidationError::GpioBank1WithoutBank0 => {
+                write!(f, "gpio bank1 can't be defined without gpio bank0")
+            }
+            ValidationError::BackPowerBank1(back_power) => write!(
+                f,
+                "back_power (bank1): {}: The back power can only be defined for bank0",
+                serde_json::to_string(back_power).expect("BUG: Can't serialize the back power")
+            ),
+            ValidationError::GpioHatEeprom(gpio) => write!(
+                f,
+                "gpio# mustn't be 0 or 1 (they are used for the HAT EEPROM): {gpio}"
+            ),
+            ValidationError::GpioOutOfRange { bank, gpio } => {
+                let (name, min, max) = match bank {
+                    gpio_map::GpioBank::Bank0 => ("bank0", 2, gpio_map::BANK0_GPIOS - 1),
+                    gpio_map::GpioBank::Bank1 => (
+                        "bank1",
+                        gpio_map::BANK0_GPIOS,
+                        gpio_map::BANK0_GPIOS + gpio_map::BANK1_GPIOS - 1,
+                    ),
+                };
+                write!(f, "gpio# ({name}): {gpio} (MIN: {min}, MAX: {max})")
+            }
+            ValidationError::GpioDuplicate(gpio) => {
+                write!(f, "gpio#: {gpio} defined more then once")
+            }
+            ValidationError::GpioInBothBanks(gpio) => {
+                write!(f, "gpio#: {gpio} defined in bank0 and bank1")
+            }
+            ValidationError::GpioCommentEmpty(gpio) => {
+                write!(f, "gpio#: {gpio} has an empty comment")
+            }
+            ValidationError::GpioUnusedConfigured(gpio) => write!(
+                f,
+                "gpio#: {gpio} is marked as unused, but its fsel or pull is configured"
+            ),
+        }
+    }
+}
+
+/// An error while parsing a [RevPiHatEeprom] or converting it into an [Eep] or back
+#[derive(Debug)]
+pub enum RevPiError {
+    /// The configuration is no valid JSON or doesn't match the expected structure
+    ///
+    /// `path` is the path of the offending field (e.g. `gpiobanks[1].gpios[3].fsel`), the line
+    /// and column are part of `error`.
+    Json {
+        path: String,
+        error: serde_json::Error,
+    },
+    /// The configuration is no valid YAML or doesn't match the expected structure (see
+    /// [RevPiError::Json])
+    Yaml {
+        path: String,
+        error: serde_yaml::Error,
+    },
+    /// A required field is not defined (`serial`, `edate` or `mac` to create an image or a
+    /// mandatory field of the [RevPiHatEepromBuilder])
+    MissingField(&'static str),
+    /// The vendor info atom can't be created
+    VendorInfo(rpi_hat_eep::EepError),
+    /// A gpio can't be added to a GPIO map atom
+    Gpio(gpio_map::GpioError),
+    /// An atom can't be added to the image
+    Push(rpi_hat_eep::EepPushError),
+    /// The image ends before the atom with the given index
+    MissingAtom { index: usize },
+    /// The atom with the given index is not of the type expected at this position
+    UnexpectedAtom {
+        index: usize,
+        atype: rpi_hat_eep::EepAtomType,
+    },
+    /// The data of the atom with the given index can't be parsed
+    InvalidAtomData { index: usize, reason: String },
+    /// The configuration is invalid
+    Validation(ValidationError),
+}
+
+impl std::error::Error for RevPiError {}
+
+impl std::fmt::Display for RevPiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RevPiError::MissingField(field) => write!(f, "The `{field}` is not defined"),
+            RevPiError::VendorInfo(e) => write!(f, "Can't create the vendor info atom: {e}"),
+            RevPiError::Gpio(e) => write!(f, "Can't create the GPIO map atom: {e}"),
+            RevPiError::Push(e) => write!(f, "Can't add the atom: {e}"),
+            RevPiError::MissingAtom { index } => write!(f, "Missing atom {index}"),
+            RevPiError::UnexpectedAtom { index, atype } => {
+                write!(f, "Unexpected atom {index}: {atype}")
+            }
+            RevPiError::InvalidAtomData { index, reason } => {
+                write!(f, "Invalid data of atom {index}: {reason}")
+            }
+            RevPiError::Json { path, error } if path == "." => write!(f, "{error}"),
+            RevPiError::Json { path, error } => write!(f, "{path}: {error}"),
+            // serde_yaml includes the path in the message already
+            RevPiError::Yaml { error, .. } => write!(f, "{error}"),
+            RevPiError::Validation(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<serde_path_to_error::Error<serde_json::Error>> for RevPiError {
+    fn from(e: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        RevPiError::Json {
+            path: e.path().to_string(),
+            error: e.into_inner(),
+        }
+    }
+}
+
+impl From<serde_path_to_error::Error<serde_yaml::Error>> for RevPiError {
+    fn from(e: serde_path_to_error::Error<serde_yaml::Error>) -> Self {
+        RevPiError::Yaml {
+            path: e.path().to_string(),
+            error: e.into_inner(),
+        }
+    }
+}
+
+impl From<ValidationError> for RevPiError {
+    fn from(e: ValidationError) -> Self {
+        RevPiError::Validation(e)
+    }
+}
+
+impl From<rpi_hat_eep::EepError> for RevPiError {
+    fn from(e: rpi_hat_eep::EepError) -> Self {
+        RevPiError::VendorInfo(e)
+    }
+}
+
+impl From<gpio_map::GpioError> for RevPiError {
+    fn from(e: gpio_map::GpioError) -> Self {
+        RevPiError::Gpio(e)
+    }
+}
+
+impl From<rpi_hat_eep::EepPushError> for RevPiError {
+    fn from(e: rpi_hat_eep::EepPushError) -> Self {
+        RevPiError::Push(e)
+    }
+}
+
+/// The RevPi HAT EEPROM format versions (`version`) supported by this crate
+pub const SUPPORTED_FORMAT_VERSIONS: &[u16] = &[1];
+
+/// The lowest `eeprom_data_version`
+pub const EEPROM_DATA_VERSION_MIN: u16 = 1;
+/// The highest `eeprom_data_version` which can be stored in the custom atom
+pub const EEPROM_DATA_VERSION_MAX: u16 = u16::MAX;
+
+/// The lowest product version `pver` (0.01)
+pub const PVER_MIN: u16 = 1;
+/// The highest product version `pver` (99.99), the major number has at most two digits
+pub const PVER_MAX: u16 = 9999;
+
+/// The severity of a validation rule
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// A violation of the rule is fatal, the configuration is rejected
+    Error,
+    /// A violation of the rule is reported, but the configuration is accepted
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A validation rule that is applied to a RevPi HAT EEPROM configuration
+///
+/// See [validation_rules()] for a list of all rules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValidationRule {
+    /// A short and stable identifier of the rule
+    pub id: &'static str,
+    /// Defines if a violation of the rule is fatal or only a warning
+    pub severity: Severity,
+    /// A human readable description of the rule
+    pub description: &'static str,
+    /// A violation of the rule is fatal in strict mode, even if it is only a warning (see
+    /// [validate_mac()])
+    pub strict: bool,
+}
+
+/// All validation rules which are applied to a configuration by [parse_config()]
+///
+/// Every new check must be registered here, so the list presented to the user stays accurate.
+const VALIDATION_RULES: &[ValidationRule] = &[
+    ValidationRule {
+        id: "format-version",
+        severity: Severity::Error,
+        description: "The format `version` must be 1",
+        strict: false,
+    },
+    ValidationRule {
+        id: "eeprom-data-version",
+        severity: Severity::Error,
+        description: "The `eeprom_data_version` must be at least 1",
+        strict: false,
+    },
+    ValidationRule {
+        id: "pver-range",
+        severity: Severity::Error,
+        description: "The product version `pver` must be in the range 1-9999 (0.01-99.99)",
+        strict: false,
+    },
+    ValidationRule {
+        id: "pstr-length",
+        severity: Severity::Error,
+        description: "The product string `pstr` must not be longer than 255 bytes",
+        strict: false,
+    },
+    ValidationRule {
+        id: "vstr-length",
+        severity: Severity::Error,
+        description: "The vendor string `vstr` must not be longer than 255 bytes",
+        strict: false,
+    },
+    ValidationRule {
+        id: "dtstr-length",
+        severity: Severity::Error,
+        description: "The device tree string `dtstr` must be shorter than 4294967295 bytes",
+        strict: false,
+    },
+    ValidationRule {
+        id: "dtstr-charset",
+        severity: Severity::Error,
+        description: "The device tree string `dtstr` must not be empty and only consist of A-Z, \
+            a-z, 0-9, `.`, `_` and `-`",
+        strict: false,
+    },
+    ValidationRule {
+        id: "mac-address",
+        severity: Severity::Error,
+        description: "The `mac` must not be all zeros, the broadcast or a multicast address",
+        strict: false,
+    },
+    ValidationRule {
+        id: "mac-local",
+        severity: Severity::Warning,
+        description: "The `mac` should not be a locally administered address",
+        strict: true,
+    },
+    ValidationRule {
+        id: "mac-count",
+        severity: Severity::Error,
+        description: "The `mac_count` must be at least 1 and all mac addresses of the block must \
+            be valid",
+        strict: false,
+    },
+    ValidationRule {
+        id: "gpiobank-count",
+        severity: Severity::Error,
+        description: "There must be one or two `gpiobanks`",
+        strict: false,
+    },
+    ValidationRule {
+        id: "gpio-hat-eeprom",
+        severity: Severity::Error,
+        description: "The gpios 0 and 1 must not be configured (they are used for the HAT EEPROM)",
+        strict: false,
+    },
+    ValidationRule {
+        id: "gpio-range",
+        severity: Severity::Error,
+        description: "The gpios of bank0 must be in the range 2-27, the ones of bank1 in the range 28-45",
+        strict: false,
+    },
+    ValidationRule {
+        id: "back-power-bank1",
+        severity: Severity::Error,
+        description: "The `back_power` can only be defined for gpio bank0",
+        strict: false,
+    },
+    ValidationRule {
+        id: "gpio-duplicate",
+        severity: Severity::Error,
+        description: "A gpio must not be configured more than once in a gpio bank",
+        strict: false,
+    },
+    ValidationRule {
+        id: "gpio-both-banks",
+        severity: Severity::Error,
+        description: "A gpio must not be configured in both gpio banks",
+        strict: false,
+    },
+    ValidationRule {
+        id: "gpio-comment",
+        severity: Severity::Error,
+        description: "A gpio `comment` must not be an empty list or contain an empty line",
+        strict: false,
+    },
+    ValidationRule {
+        id: "gpio-unused",
+        severity: Severity::Warning,
+        description: "A gpio marked as unused (`used`: false) should leave `fsel` and `pull` at \
+            their defaults",
+        strict: false,
+    },
+];
+
+/// Return all validation rules which are applied to a configuration
+pub fn validation_rules() -> &'static [ValidationRule] {
+    VALIDATION_RULES
+}
+
+#[test]
+fn test_validation_rules() {
+    let rules = validation_rules();
+    assert!(!rules.is_empty());
+    for (i, rule) in rules.iter().enumerate() {
+        assert!(!rule.id.is_empty());
+        assert!(!rule.description.is_empty());
+        assert!(
+            rules[i + 1..].iter().all(|r| r.id != rule.id),
+            "duplicate rule id: {}",
+            rule.id
+        );
     }
 }
 
@@ -85,7 +533,8 @@ impl std::fmt::Display for ValidationError {
 /// }
 /// ```
 ///
-#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct RevPiHatEeprom {
     /// The version of the used [RevPi HAT EEPROM Format](https://github.com/RevolutionPi/revpi-hat-eeprom/blob/master/docs/RevPi-HAT-EEPROM-Format.md#0-format-version)
@@ -109,53 +558,1399 @@ pub struct RevPiHatEeprom {
     /// The end test date represents the current date as of when the end of line test is/was done, see [Endtest Date](https://github.com/RevolutionPi/revpi-hat-eeprom/blob/master/docs/RevPi-HAT-EEPROM-Format.md#3-endtest-date)
     pub edate: Option<NaiveDate>,
     /// The first mac address of the device, see [MAC Address](https://github.com/RevolutionPi/revpi-hat-eeprom/blob/master/docs/RevPi-HAT-EEPROM-Format.md#5-mac-address)
+    #[cfg_attr(feature = "schema", schemars(with = "Option<[u8; 6]>"))]
     pub mac: Option<MacAddr6>,
+    /// The number of consecutive mac addresses of the device starting with `mac` (default: 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mac_count: Option<u8>,
     /// The configuration of the first gpiobank, see [GPIO map atom data](https://github.com/RevolutionPi/revpi-hat-eeprom/blob/master/docs/RevPi-HAT-EEPROM-Format.md#gpio-map-atom-data-type0x0002)
+    ///
+    /// The gpiobanks may be omitted if they are supplied separately (see
+    /// [parse_config_with_gpio_banks()]).
+    #[serde(default)]
     pub gpiobanks: Vec<GpioBank>,
 }
 
-pub fn parse_config(s: &str) -> Result<RevPiHatEeprom, Box<dyn std::error::Error>> {
-    let eep: RevPiHatEeprom = serde_json::from_str(s)?;
+/// Return the JSON schema of the configuration format
+///
+/// The schema only describes the structure of a configuration. The [validation_rules()] that
+/// depend on more than one field are not covered.
+#[cfg(feature = "schema")]
+pub fn config_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(RevPiHatEeprom))
+        .expect("BUG: Can't serialize the config schema")
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn test_config_schema() {
+    let schema = config_schema();
+    let compiled = jsonschema::JSONSchema::compile(&schema).unwrap();
+    let example: serde_json::Value =
+        serde_json::from_str(include_str!("../../docs/example.json")).unwrap();
+    assert!(compiled.is_valid(&example));
+
+    // The enum values must be the same as the serde names
+    let drive = &schema["definitions"]["GpioBankDrive"].to_string();
+    assert!(drive.contains(r#""8mA""#));
+    let fsel = &schema["definitions"]["GpioFsel"].to_string();
+    assert!(fsel.contains(r#""alt1""#));
+
+    let mut invalid = example.clone();
+    invalid["gpiobanks"][0]["drive"] = "9mA".into();
+    assert!(!compiled.is_valid(&invalid));
+    let mut invalid = example;
+    invalid["unknown"] = 1.into();
+    assert!(!compiled.is_valid(&invalid));
+}
+
+/// The index of the first RevPi custom atom (custom_0) in an image
+const FIRST_CUSTOM_ATOM: usize = 3;
+
+/// The index of the optional mac count (custom_7) in an image
+const MAC_COUNT_ATOM: usize = FIRST_CUSTOM_ATOM + RevPiCustomField::ALL.len();
+
+/// The RevPi custom atoms in the order they are written to an image (custom_0...custom_6)
+///
+/// All values are written as ASCII strings, see [Custom Atoms](https://github.com/RevolutionPi/revpi-hat-eeprom/blob/master/docs/RevPi-HAT-EEPROM-Format.md#custom-atoms).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RevPiCustomField {
+    /// custom_0: the format `version`
+    FormatVersion,
+    /// custom_1: the `serial`
+    Serial,
+    /// custom_2: the product revision `prev`
+    ProductRevision,
+    /// custom_3: the end test date `edate`
+    EndtestDate,
+    /// custom_4: the LOT/batch number (always `0`)
+    Lot,
+    /// custom_5: the first `mac` address
+    FirstMac,
+    /// custom_6: the `eeprom_data_version`
+    EepromDataVersion,
+    /// custom_7: the `mac_count` (only written if it is defined)
+    MacCount,
+}
+
+impl RevPiCustomField {
+    /// The custom fields written to every image in the order of the image
+    ///
+    /// The optional [RevPiCustomField::MacCount] follows them if the `mac_count` is defined.
+    pub const ALL: [RevPiCustomField; 7] = [
+        RevPiCustomField::FormatVersion,
+        RevPiCustomField::Serial,
+        RevPiCustomField::ProductRevision,
+        RevPiCustomField::EndtestDate,
+        RevPiCustomField::Lot,
+        RevPiCustomField::FirstMac,
+        RevPiCustomField::EepromDataVersion,
+    ];
+
+    /// Return the field of the custom atom custom_`n`
+    pub fn from_index(n: usize) -> Option<RevPiCustomField> {
+        match n {
+            7 => Some(RevPiCustomField::MacCount),
+            n => RevPiCustomField::ALL.get(n).copied(),
+        }
+    }
+
+    /// Return the `n` of the custom atom custom_`n`
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Return the human readable name of the field
+    pub fn name(self) -> &'static str {
+        match self {
+            RevPiCustomField::FormatVersion => "format version",
+            RevPiCustomField::Serial => "serial",
+            RevPiCustomField::ProductRevision => "product revision",
+            RevPiCustomField::EndtestDate => "endtest date",
+            RevPiCustomField::Lot => "LOT/batch",
+            RevPiCustomField::FirstMac => "mac address",
+            RevPiCustomField::EepromDataVersion => "EEPROM data version",
+            RevPiCustomField::MacCount => "mac count",
+        }
+    }
+
+    /// Create the custom atom data of this field from a config
+    ///
+    /// The `serial`, `edate`, `mac` and `mac_count` must be defined for the respective field.
+    pub fn encode(self, config: &RevPiHatEeprom) -> Result<EepAtomCustomData, RevPiError> {
+        let value = match self {
+            RevPiCustomField::FormatVersion => config.version.to_string(),
+            RevPiCustomField::Serial => config
+                .serial
+                .ok_or(RevPiError::MissingField("serial"))?
+                .to_string(),
+            RevPiCustomField::ProductRevision => config.prev.to_string(),
+            RevPiCustomField::EndtestDate => config
+                .edate
+                .ok_or(RevPiError::MissingField("edate"))?
+                .to_string(),
+            // The LOT/batch number is not part of the configuration
+            RevPiCustomField::Lot => "0".to_string(),
+            RevPiCustomField::FirstMac => config
+                .mac
+                .ok_or(RevPiError::MissingField("mac"))?
+                .to_string(),
+            RevPiCustomField::EepromDataVersion => config.eeprom_data_version.to_string(),
+            RevPiCustomField::MacCount => config
+                .mac_count
+                .ok_or(RevPiError::MissingField("mac_count"))?
+                .to_string(),
+        };
+        Ok(EepAtomCustomData::new(value.into_bytes()))
+    }
+
+    /// Parse the value of this field from the custom atom data
+    pub fn decode<T>(self, data: &EepAtomCustomData) -> Result<T, String>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        std::str::from_utf8(data.data())
+            .map_err(|e| e.to_string())?
+            .parse()
+            .map_err(|e: T::Err| format!("custom_{}: {e}", self.index()))
+    }
+}
+
+impl std::fmt::Display for RevPiCustomField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[test]
+fn test_custom_field() {
+    let mut config = test_config();
+    config.serial = None;
+    assert!(matches!(
+        RevPiCustomField::Serial.encode(&config),
+        Err(RevPiError::MissingField("serial"))
+    ));
+    config.serial = Some(4711);
+
+    let expected = [
+        "1",
+        "4711",
+        "3",
+        "2024-01-01",
+        "0",
+        "C8:3E:A7:00:00:01",
+        "3",
+    ];
+    for (n, field) in RevPiCustomField::ALL.into_iter().enumerate() {
+        assert_eq!(field.index(), n);
+        assert_eq!(RevPiCustomField::from_index(n), Some(field));
+        assert_eq!(
+            field.encode(&config).unwrap().data(),
+            expected[n].as_bytes()
+        );
+    }
+    assert_eq!(
+        RevPiCustomField::from_index(7),
+        Some(RevPiCustomField::MacCount)
+    );
+    assert_eq!(RevPiCustomField::from_index(8), None);
+    assert_eq!(RevPiCustomField::FirstMac.to_string(), "mac address");
+
+    let data = RevPiCustomField::EndtestDate.encode(&config).unwrap();
+    assert_eq!(
+        RevPiCustomField::EndtestDate.decode::<NaiveDate>(&data),
+        Ok(config.edate.unwrap())
+    );
+    let data = EepAtomCustomData::new(b"x".to_vec());
+    assert!(RevPiCustomField::Serial
+        .decode::<u32>(&data)
+        .unwrap_err()
+        .starts_with("custom_1: "));
+}
+
+/// Derive the UUID of a device from its product data and serial
+///
+/// The UUID is a version 3 (MD5) UUID. The MD5 digest is calculated over these 10 bytes:
+/// ```text
+/// Bytes   Field
+/// 2       pid     little endian
+/// 2       pver    little endian
+/// 2       prev    little endian
+/// 4       serial  little endian
+/// ```
+/// The version and variant bits of the digest are then set as defined by RFC 4122.
+///
+/// The UUID is written to the EEPROM of every device and identifies it forever, so the result of
+/// this function must never change.
+pub fn derive_uuid(pid: u16, pver: u16, prev: u16, serial: u32) -> uuid::Uuid {
+    let mut bytes: Vec<u8> = Vec::with_capacity(10);
+    bytes.extend_from_slice(&u16::to_le_bytes(pid));
+    bytes.extend_from_slice(&u16::to_le_bytes(pver));
+    bytes.extend_from_slice(&u16::to_le_bytes(prev));
+    bytes.extend_from_slice(&u32::to_le_bytes(serial));
+    let digest = md5::compute(&bytes);
+    uuid::Builder::from_md5_bytes(*digest).into_uuid()
+}
+
+#[test]
+fn test_derive_uuid() {
+    // These values must never change, they are written to the devices
+    assert_eq!(
+        derive_uuid(666, 333, 3, 1).to_string(),
+        "aba68c9a-1352-3d5d-8548-b813bc0ad5fb"
+    );
+    assert_eq!(
+        derive_uuid(0, 0, 0, 0).to_string(),
+        "a63c90cc-3684-3d8b-8a21-76a6a8fe9005"
+    );
+    assert_eq!(
+        derive_uuid(302, 100, 1, 4711).to_string(),
+        "d2abb25e-01fe-3f15-9cc7-0b004fc14f2f"
+    );
+    assert_eq!(
+        derive_uuid(302, 100, 1, 4711).get_version(),
+        Some(uuid::Version::Md5)
+    );
+    assert_ne!(
+        derive_uuid(302, 100, 1, 4711),
+        derive_uuid(302, 100, 1, 4712)
+    );
+}
+
+/// Return the part number of a product, e.g. `PR100302R01`
+pub fn part_number(pid: u16, prev: u16) -> String {
+    format!("PR1{pid:05}R{prev:02}")
+}
+
+#[test]
+fn test_part_number() {
+    assert_eq!(part_number(302, 1), "PR100302R01");
+    assert_eq!(part_number(0, 0), "PR100000R00");
+}
+
+impl RevPiHatEeprom {
+    /// Return a builder to create a configuration without JSON
+    pub fn builder() -> RevPiHatEepromBuilder {
+        RevPiHatEepromBuilder::default()
+    }
+
+    /// Create a summary of the device with the per device values `serial`, `edate` and `mac`
+    ///
+    /// The per device values are passed separately, as they are often not part of the
+    /// configuration file.
+    pub fn summary(
+        &self,
+        serial: u32,
+        edate: Option<NaiveDate>,
+        mac: Option<MacAddr6>,
+    ) -> EepromSummary {
+        EepromSummary {
+            vstr: self.vstr.clone(),
+            pstr: self.pstr.clone(),
+            part_number: part_number(self.pid, self.prev),
+            pver: self.pver,
+            serial,
+            edate,
+            mac,
+            uuid: derive_uuid(self.pid, self.pver, self.prev, serial),
+            dtstr: self.dtstr.clone(),
+            banks: self.gpiobanks.len(),
+            image_len: None,
+        }
+    }
+
+    /// Apply the [validation_rules()] again, e.g. after fields were changed after parsing
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        validate(self)
+    }
+
+    /// Return the violations of the [validation_rules()] with the severity
+    /// [warning](Severity::Warning)
+    ///
+    /// Unlike the errors of [RevPiHatEeprom::validate()], they don't prevent creating the image.
+    pub fn warnings(&self) -> Vec<ValidationError> {
+        self.gpiobanks.iter().flat_map(GpioBank::warnings).collect()
+    }
+
+    /// Create the image of the configuration
+    ///
+    /// The `serial`, `edate` and `mac` must be defined. The atoms are the vendor info, the gpio bank
+    /// 0 map, the device tree overlay name, the RevPi custom atoms (custom_0...custom_6), the
+    /// optional mac count (custom_7) and the optional gpio bank 1 map.
+    pub fn to_eep(&self) -> Result<Eep, RevPiError> {
+        self.to_eep_with_uuid_byte_order(UuidByteOrder::Reversed)
+    }
+
+    /// Create the image of the configuration with the given byte order of the UUID
+    ///
+    /// This is the same as [RevPiHatEeprom::to_eep()]. Only use [UuidByteOrder::Normal] to create
+    /// images for interoperability tests.
+    pub fn to_eep_with_uuid_byte_order(
+        &self,
+        uuid_byte_order: UuidByteOrder,
+    ) -> Result<Eep, RevPiError> {
+        let serial = self.serial.ok_or(RevPiError::MissingField("serial"))?;
+        // The edate and mac are written by the custom atoms, but are checked up front
+        self.edate.ok_or(RevPiError::MissingField("edate"))?;
+        self.mac.ok_or(RevPiError::MissingField("mac"))?;
+
+        let uuid = derive_uuid(self.pid, self.pver, self.prev, serial);
+        let mut vendor_data = EepAtomVendorData::new(
+            uuid,
+            self.pid,
+            self.pver,
+            self.vstr.clone(),
+            self.pstr.clone(),
+        )?;
+        vendor_data.set_uuid_byte_order(uuid_byte_order);
+
+        let gpio_bank0_map = self.gpiobanks[0].to_gpio_map(gpio_map::GpioBank::Bank0)?;
+        let mut eep = Eep::new(vendor_data, gpio_bank0_map);
+
+        let dtb = EepAtomLinuxDTBData::new(LinuxDTB::Name(self.dtstr.clone()));
+        eep.push(EepAtom::new_linux_dtb(dtb))?;
+
+        for field in RevPiCustomField::ALL {
+            eep.push(EepAtom::new_custom(field.encode(self)?))?;
+        }
+        if self.mac_count.is_some() {
+            eep.push(EepAtom::new_custom(
+                RevPiCustomField::MacCount.encode(self)?,
+            ))?;
+        }
+
+        if self.gpiobanks.len() > 1 {
+            let gpio_bank1_map = self.gpiobanks[1].to_gpio_map(gpio_map::GpioBank::Bank1)?;
+            eep.push(EepAtom::new_gpio_bank1_map(gpio_bank1_map))?;
+        }
+        Ok(eep)
+    }
+
+    /// Reconstruct the configuration from an image written by `revpi-eep`
+    ///
+    /// The atoms must be in the order `revpi-eep` writes them: vendor info, gpio bank 0 map, device
+    /// tree overlay name, the RevPi custom atoms (custom_0...custom_6), the optional mac count
+    /// (custom_7) and the optional gpio bank 1 map. The comments of the gpios are not part of the image and can't be restored. The
+    /// reconstructed configuration is validated like a parsed one.
+    pub fn from_eep(eep: &Eep) -> Result<Self, RevPiError> {
+        let atoms = eep.atoms();
+
+        let vendor = match atom_data(eep, 0)? {
+            EepAtomData::VendorInfo(vendor) => vendor,
+            _ => return Err(unexpected_atom(eep, 0)),
+        };
+        let gpio_bank0 = match atom_data(eep, 1)? {
+            EepAtomData::GpioBank0Map(gpio_map) => GpioBank::from_gpio_map(gpio_map),
+            _ => return Err(unexpected_atom(eep, 1)),
+        };
+        let dtstr = match atom_data(eep, 2)? {
+            EepAtomData::LinuxDTB(dtb) => match dtb.data() {
+                LinuxDTB::Name(name) => name.clone(),
+                LinuxDTB::Blob(blob) => {
+                    String::from_utf8(blob.clone()).map_err(|e| RevPiError::InvalidAtomData {
+                        index: 2,
+                        reason: e.to_string(),
+                    })?
+                }
+            },
+            _ => return Err(unexpected_atom(eep, 2)),
+        };
+
+        let mut mac_count = None;
+        let mut gpio_bank1_atom = MAC_COUNT_ATOM;
+        if let Some(EepAtomData::ManufCustomData(_)) = atoms.get(MAC_COUNT_ATOM).map(|a| a.data()) {
+            mac_count = Some(custom_atom(eep, RevPiCustomField::MacCount)?);
+            gpio_bank1_atom += 1;
+        }
+
+        let mut gpiobanks = vec![gpio_bank0];
+        if let Some(atom) = atoms.get(gpio_bank1_atom) {
+            match atom.data() {
+                EepAtomData::GpioBank1Map(gpio_map) => {
+                    gpiobanks.push(GpioBank::from_gpio_map(gpio_map))
+                }
+                _ => return Err(unexpected_atom(eep, gpio_bank1_atom)),
+            }
+        }
+        if atoms.len() > gpio_bank1_atom + 1 {
+            return Err(unexpected_atom(eep, gpio_bank1_atom + 1));
+        }
+
+        let config = RevPiHatEeprom {
+            version: custom_atom(eep, RevPiCustomField::FormatVersion)?,
+            eeprom_data_version: custom_atom(eep, RevPiCustomField::EepromDataVersion)?,
+            vstr: vendor.vstr().to_string(),
+            pstr: vendor.pstr().to_string(),
+            pid: vendor.pid(),
+            prev: custom_atom(eep, RevPiCustomField::ProductRevision)?,
+            pver: vendor.pver(),
+            dtstr,
+            serial: Some(custom_atom(eep, RevPiCustomField::Serial)?),
+            edate: Some(custom_atom(eep, RevPiCustomField::EndtestDate)?),
+            mac: Some(custom_atom(eep, RevPiCustomField::FirstMac)?),
+            mac_count,
+            gpiobanks,
+        };
+        // custom_4 (LOT/batch) is always `0` and not part of the configuration
+        validate(&config)?;
+        Ok(config)
+    }
+}
+
+/// A builder for a [RevPiHatEeprom] (see [RevPiHatEeprom::builder()])
+///
+/// The fields of the configuration file are mandatory, except the `serial`, `edate` and `mac`.
+/// [build()](RevPiHatEepromBuilder::build) applies the same validation as [parse_config()].
+#[derive(Clone, Debug, Default)]
+pub struct RevPiHatEepromBuilder {
+    version: Option<u16>,
+    eeprom_data_version: Option<u16>,
+    vstr: Option<String>,
+    pstr: Option<String>,
+    pid: Option<u16>,
+    prev: Option<u16>,
+    pver: Option<u16>,
+    dtstr: Option<String>,
+    serial: Option<u32>,
+    edate: Option<NaiveDate>,
+    mac: Option<MacAddr6>,
+    mac_count: Option<u8>,
+    gpiobanks: Option<Vec<GpioBank>>,
+}
+
+impl RevPiHatEepromBuilder {
+    pub fn version(mut self, version: u16) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    pub fn eeprom_data_version(mut self, eeprom_data_version: u16) -> Self {
+        self.eeprom_data_version = Some(eeprom_data_version);
+        self
+    }
+
+    pub fn vstr(mut self, vstr: impl Into<String>) -> Self {
+        self.vstr = Some(vstr.into());
+        self
+    }
+
+    pub fn pstr(mut self, pstr: impl Into<String>) -> Self {
+        self.pstr = Some(pstr.into());
+        self
+    }
+
+    pub fn pid(mut self, pid: u16) -> Self {
+        self.pid = Some(pid);
+        self
+    }
+
+    pub fn prev(mut self, prev: u16) -> Self {
+        self.prev = Some(prev);
+        self
+    }
+
+    pub fn pver(mut self, pver: u16) -> Self {
+        self.pver = Some(pver);
+        self
+    }
+
+    pub fn dtstr(mut self, dtstr: impl Into<String>) -> Self {
+        self.dtstr = Some(dtstr.into());
+        self
+    }
+
+    pub fn serial(mut self, serial: u32) -> Self {
+        self.serial = Some(serial);
+        self
+    }
+
+    pub fn edate(mut self, edate: NaiveDate) -> Self {
+        self.edate = Some(edate);
+        self
+    }
+
+    pub fn mac(mut self, mac: MacAddr6) -> Self {
+        self.mac = Some(mac);
+        self
+    }
+
+    pub fn mac_count(mut self, mac_count: u8) -> Self {
+        self.mac_count = Some(mac_count);
+        self
+    }
+
+    /// Set the gpio bank 0 and optionally the gpio bank 1
+    pub fn gpiobanks(mut self, gpiobanks: Vec<GpioBank>) -> Self {
+        self.gpiobanks = Some(gpiobanks);
+        self
+    }
+
+    /// Create the configuration and validate it
+    pub fn build(self) -> Result<RevPiHatEeprom, RevPiError> {
+        let config = RevPiHatEeprom {
+            version: self.version.ok_or(RevPiError::MissingField("version"))?,
+            eeprom_data_version: self
+                .eeprom_data_version
+                .ok_or(RevPiError::MissingField("eeprom_data_version"))?,
+            vstr: self.vstr.ok_or(RevPiError::MissingField("vstr"))?,
+            pstr: self.pstr.ok_or(RevPiError::MissingField("pstr"))?,
+            pid: self.pid.ok_or(RevPiError::MissingField("pid"))?,
+            prev: self.prev.ok_or(RevPiError::MissingField("prev"))?,
+            pver: self.pver.ok_or(RevPiError::MissingField("pver"))?,
+            dtstr: self.dtstr.ok_or(RevPiError::MissingField("dtstr"))?,
+            serial: self.serial,
+            edate: self.edate,
+            mac: self.mac,
+            mac_count: self.mac_count,
+            gpiobanks: self
+                .gpiobanks
+                .ok_or(RevPiError::MissingField("gpiobanks"))?,
+        };
+        validate(&config)?;
+        Ok(config)
+    }
+}
+
+#[test]
+fn test_builder() {
+    let example = test_config();
+    let builder = RevPiHatEeprom::builder()
+        .version(1)
+        .eeprom_data_version(3)
+        .vstr("KUNBUS GmbH")
+        .pstr("RevPi ExampleDevice 8GB")
+        .pid(666)
+        .prev(3)
+        .pver(333)
+        .dtstr("revpi-example-2022")
+        .gpiobanks(example.gpiobanks.clone());
+    let config = builder
+        .clone()
+        .serial(1)
+        .edate(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap())
+        .mac("c8:3e:a7:00:00:01".parse().unwrap())
+        .build()
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(&config).unwrap(),
+        serde_json::to_value(&example).unwrap()
+    );
+
+    // The per device fields are optional, but can be set
+    let config = builder.clone().build().unwrap();
+    assert_eq!(
+        (config.serial, config.edate, config.mac),
+        (None, None, None)
+    );
+    let config = builder
+        .clone()
+        .serial(4711)
+        .mac("c8:3e:a7:00:00:01".parse().unwrap())
+        .build()
+        .unwrap();
+    assert_eq!(config.serial, Some(4711));
+    assert!(config.to_eep().is_err());
+
+    // Mandatory fields
+    assert!(matches!(
+        RevPiHatEeprom::builder().build(),
+        Err(RevPiError::MissingField("version"))
+    ));
+    let mut missing = builder.clone();
+    missing.gpiobanks = None;
+    assert!(matches!(
+        missing.build(),
+        Err(RevPiError::MissingField("gpiobanks"))
+    ));
+
+    // The configuration is validated
+    assert!(matches!(
+        builder.clone().pver(0).build(),
+        Err(RevPiError::Validation(ValidationError::InvalidPver(0)))
+    ));
+    assert!(matches!(
+        builder.gpiobanks(vec![]).build(),
+        Err(RevPiError::Validation(ValidationError::GpioBankCount(0)))
+    ));
+}
+
+/// A human readable summary of a device (see [RevPiHatEeprom::summary()])
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EepromSummary {
+    pub vstr: String,
+    pub pstr: String,
+    /// The part number, see [part_number()]
+    pub part_number: String,
+    pub pver: u16,
+    pub serial: u32,
+    pub edate: Option<NaiveDate>,
+    pub mac: Option<MacAddr6>,
+    pub uuid: uuid::Uuid,
+    pub dtstr: String,
+    /// The number of gpio banks
+    pub banks: usize,
+    /// The size of the image in bytes, if it is already created
+    pub image_len: Option<usize>,
+}
+
+impl EepromSummary {
+    /// Add the size of the created image
+    pub fn with_image_len(mut self, image_len: usize) -> Self {
+        self.image_len = Some(image_len);
+        self
+    }
+}
+
+impl std::fmt::Display for EepromSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "product: {} {} ({})",
+            self.vstr, self.pstr, self.part_number
+        )?;
+        write!(f, "\npver:    {}", self.pver)?;
+        write!(f, "\nserial:  {}", self.serial)?;
+        if let Some(edate) = self.edate {
+            write!(f, "\nedate:   {edate}")?;
+        }
+        if let Some(mac) = self.mac {
+            write!(f, "\nmac:     {mac}")?;
+        }
+        write!(f, "\nuuid:    {}", self.uuid)?;
+        write!(f, "\ndtstr:   {}", self.dtstr)?;
+        write!(f, "\nbanks:   {}", self.banks)?;
+        if let Some(image_len) = self.image_len {
+            write!(f, "\nimage:   {image_len} bytes")?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_summary() {
+    let config = test_config();
+    let summary = config.summary(
+        1,
+        Some(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
+        Some("c8:3e:a7:00:00:01".parse().unwrap()),
+    );
+    assert_eq!(summary.part_number, "PR100666R03");
+    assert_eq!(summary.uuid, derive_uuid(666, 333, 3, 1));
+    assert_eq!(
+        summary.clone().with_image_len(278).to_string(),
+        format!(
+            "product: KUNBUS GmbH RevPi ExampleDevice 8GB (PR100666R03)\n\
+            pver:    333\n\
+            serial:  1\n\
+            edate:   2024-01-01\n\
+            mac:     C8:3E:A7:00:00:01\n\
+            uuid:    {}\n\
+            dtstr:   revpi-example-2022\n\
+            banks:   2\n\
+            image:   278 bytes",
+            summary.uuid
+        )
+    );
+
+    // The optional lines are left out
+    let summary = config.summary(1, None, None).to_string();
+    assert!(!summary.contains("edate:"));
+    assert!(!summary.contains("mac:"));
+    assert!(summary.ends_with("\nbanks:   2"));
+}
+
+fn atom_data(eep: &Eep, index: usize) -> Result<&EepAtomData, RevPiError> {
+    eep.atoms()
+        .get(index)
+        .map(|atom| atom.data())
+        .ok_or(RevPiError::MissingAtom { index })
+}
+
+fn unexpected_atom(eep: &Eep, index: usize) -> RevPiError {
+    RevPiError::UnexpectedAtom {
+        index,
+        atype: eep.atoms()[index].atype(),
+    }
+}
+
+/// Parse the RevPi custom atom of a field
+fn custom_atom<T>(eep: &Eep, field: RevPiCustomField) -> Result<T, RevPiError>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let index = FIRST_CUSTOM_ATOM + field.index();
+    let data = match atom_data(eep, index)? {
+        EepAtomData::ManufCustomData(data) => data,
+        _ => return Err(unexpected_atom(eep, index)),
+    };
+    field
+        .decode(data)
+        .map_err(|reason| RevPiError::InvalidAtomData { index, reason })
+}
+
+/// The example config with the serial 1, the edate 2024-01-01 and the mac c8:3e:a7:00:00:01
+#[cfg(test)]
+fn test_config() -> RevPiHatEeprom {
+    let mut config = parse_config(include_str!("../../docs/example.json")).unwrap();
+    config.serial = Some(1);
+    config.edate = Some(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+    config.mac = Some("c8:3e:a7:00:00:01".parse().unwrap());
+    config
+}
+
+#[test]
+fn test_to_eep() {
+    let mut config = test_config();
+    config.mac = None;
+    assert!(matches!(
+        config.to_eep(),
+        Err(RevPiError::MissingField("mac"))
+    ));
+    config.mac = test_config().mac;
+    let eep = config.to_eep().unwrap();
+    assert_eq!(eep.atom_count(), 3 + 7 + 1);
+    let decoded = RevPiHatEeprom::from_eep(&eep).unwrap();
+    assert_eq!(decoded.serial, config.serial);
+
+    config.serial = None;
+    assert!(matches!(
+        config.to_eep(),
+        Err(RevPiError::MissingField("serial"))
+    ));
+}
+
+#[test]
+fn test_from_eep_missing_atoms() {
+    let vendor = rpi_hat_eep::EepAtomVendorData::new(
+        uuid::Uuid::nil(),
+        666,
+        333,
+        "KUNBUS GmbH".to_string(),
+        "RevPi ExampleDevice".to_string(),
+    )
+    .unwrap();
+    let gpio_map = gpio_map::EepAtomGpioMapData::new(
+        gpio_map::GpioBank::Bank0,
+        gpio_map::GpioDrive::Default,
+        gpio_map::GpioSlew::Default,
+        gpio_map::GpioHysteresis::Default,
+        gpio_map::GpioBackPower::None,
+    );
+    let mut eep = Eep::new(vendor, gpio_map);
+    assert!(matches!(
+        RevPiHatEeprom::from_eep(&eep),
+        Err(RevPiError::MissingAtom { index: 2 })
+    ));
+
+    // A custom atom where the DT overlay name is expected
+    let data = rpi_hat_eep::EepAtomCustomData::new(b"1".to_vec());
+    eep.push(rpi_hat_eep::EepAtom::new_custom(data)).unwrap();
+    assert!(matches!(
+        RevPiHatEeprom::from_eep(&eep),
+        Err(RevPiError::UnexpectedAtom {
+            index: 2,
+            atype: rpi_hat_eep::EepAtomType::ManufCustomData
+        })
+    ));
+}
+
+/// Deserialize `s` and keep the path of the offending field on errors
+fn from_json<'a, T: Deserialize<'a>>(s: &'a str) -> Result<T, RevPiError> {
+    let mut de = serde_json::Deserializer::from_str(s);
+    let value = serde_path_to_error::deserialize(&mut de)?;
+    de.end().map_err(|error| RevPiError::Json {
+        path: ".".to_string(),
+        error,
+    })?;
+    Ok(value)
+}
+
+/// Deserialize YAML and keep the path of the offending field on errors
+fn from_yaml<'a, T: Deserialize<'a>>(s: &'a str) -> Result<T, RevPiError> {
+    Ok(serde_path_to_error::deserialize(
+        serde_yaml::Deserializer::from_str(s),
+    )?)
+}
+
+/// The format of a configuration or gpio bank file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Select the format by the extension of a file name: `.yaml` and `.yml` are YAML, everything
+    /// else is JSON
+    pub fn from_path(path: &std::path::Path) -> ConfigFormat {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    fn deserialize<'a, T: Deserialize<'a>>(self, s: &'a str) -> Result<T, RevPiError> {
+        match self {
+            ConfigFormat::Json => from_json(s),
+            ConfigFormat::Yaml => from_yaml(s),
+        }
+    }
+}
+
+#[test]
+fn test_config_format_from_path() {
+    use std::path::Path;
+    assert_eq!(
+        ConfigFormat::from_path(Path::new("a.json")),
+        ConfigFormat::Json
+    );
+    assert_eq!(
+        ConfigFormat::from_path(Path::new("a.yaml")),
+        ConfigFormat::Yaml
+    );
+    assert_eq!(
+        ConfigFormat::from_path(Path::new("a/b.yml")),
+        ConfigFormat::Yaml
+    );
+    assert_eq!(ConfigFormat::from_path(Path::new("-")), ConfigFormat::Json);
+}
+
+pub fn parse_config(s: &str) -> Result<RevPiHatEeprom, RevPiError> {
+    parse_config_with_gpio_banks(s, None, None)
+}
+
+/// Parse a configuration and replace its gpio banks with separately defined ones
+///
+/// `bank0` and `bank1` are JSON objects of a single gpio bank. If given, they replace the
+/// respective bank of the configuration or add it, if the configuration doesn't define it. The
+/// validation is done after the gpio banks are combined.
+pub fn parse_config_with_gpio_banks(
+    s: &str,
+    bank0: Option<&str>,
+    bank1: Option<&str>,
+) -> Result<RevPiHatEeprom, RevPiError> {
+    parse_config_files(
+        (s, ConfigFormat::Json),
+        bank0.map(|bank0| (bank0, ConfigFormat::Json)),
+        bank1.map(|bank1| (bank1, ConfigFormat::Json)),
+    )
+}
+
+/// Like [parse_config_with_gpio_banks()], but every file is given with its [ConfigFormat]
+pub fn parse_config_files(
+    config: (&str, ConfigFormat),
+    bank0: Option<(&str, ConfigFormat)>,
+    bank1: Option<(&str, ConfigFormat)>,
+) -> Result<RevPiHatEeprom, RevPiError> {
+    let mut eep: RevPiHatEeprom = config.1.deserialize(config.0)?;
+    if let Some((bank0, format)) = bank0 {
+        let bank0: GpioBank = format.deserialize(bank0)?;
+        if eep.gpiobanks.is_empty() {
+            eep.gpiobanks.push(bank0);
+        } else {
+            eep.gpiobanks[0] = bank0;
+        }
+    }
+    if let Some((bank1, format)) = bank1 {
+        let bank1: GpioBank = format.deserialize(bank1)?;
+        match eep.gpiobanks.len() {
+            0 => {
+                return Err(RevPiError::Validation(
+                    ValidationError::GpioBank1WithoutBank0,
+                ))
+            }
+            1 => eep.gpiobanks.push(bank1),
+            _ => eep.gpiobanks[1] = bank1,
+        }
+    }
     validate(&eep)?;
     Ok(eep)
 }
 
+#[test]
+fn test_validation_error_display() {
+    assert_eq!(
+        ValidationError::UnsupportedFormatVersion(2).to_string(),
+        "invalid value: `2`: Unsupported format version"
+    );
+    assert_eq!(
+        ValidationError::VstrTooLong("x".repeat(256)).to_string(),
+        format!(
+            "invalid value: `{}`: Vendor string to long: 256 (max: 255) bytes",
+            "x".repeat(256)
+        )
+    );
+    assert_eq!(
+        ValidationError::BackPowerBank1(gpio::GpioBankBackPower::BackPower2A).to_string(),
+        "back_power (bank1): \"2A\": The back power can only be defined for bank0"
+    );
+    assert_eq!(
+        ValidationError::GpioOutOfRange {
+            bank: gpio_map::GpioBank::Bank0,
+            gpio: 28
+        }
+        .to_string(),
+        "gpio# (bank0): 28 (MIN: 2, MAX: 27)"
+    );
+    assert_eq!(
+        ValidationError::GpioOutOfRange {
+            bank: gpio_map::GpioBank::Bank1,
+            gpio: 46
+        }
+        .to_string(),
+        "gpio# (bank1): 46 (MIN: 28, MAX: 45)"
+    );
+}
+
+#[test]
+fn test_parse_config_json_error() {
+    let config = include_str!("../../docs/example.json").replacen(
+        r#""fsel": "alt1""#,
+        r#""fsel": "alt9""#,
+        1,
+    );
+    let e = parse_config(&config).unwrap_err();
+    assert!(
+        matches!(&e, RevPiError::Json { path, .. } if path == "gpiobanks[0].gpios[2].fsel"),
+        "{e:?}"
+    );
+    let msg = e.to_string();
+    assert!(msg.starts_with("gpiobanks[0].gpios[2].fsel: unknown variant `alt9`"));
+    assert!(msg.ends_with("at line 28 column 34"), "{msg}");
+
+    // Trailing garbage has no field path
+    let e = parse_config(&format!("{} x", include_str!("../../docs/example.json"))).unwrap_err();
+    assert!(matches!(&e, RevPiError::Json { path, .. } if path == "."));
+    assert!(e.to_string().starts_with("trailing characters"));
+}
+
+#[test]
+fn test_pver() {
+    let config = |pver: u16| {
+        include_str!("../../docs/example.json")
+            .replace(r#""pver": 333"#, &format!(r#""pver": {pver}"#))
+    };
+    for pver in [PVER_MIN, 120, PVER_MAX] {
+        assert_eq!(parse_config(&config(pver)).unwrap().pver, pver);
+    }
+    for pver in [0, PVER_MAX + 1, u16::MAX] {
+        assert!(matches!(
+            parse_config(&config(pver)),
+            Err(RevPiError::Validation(ValidationError::InvalidPver(v))) if v == pver
+        ));
+    }
+    assert_eq!(
+        ValidationError::InvalidPver(10005).to_string(),
+        "invalid value: `10005`: Product version 100.05 (pver / 100) must be in the range 0.01 - 99.99"
+    );
+}
+
+#[test]
+fn test_dtstr() {
+    let config = |dtstr: &str| {
+        include_str!("../../docs/example.json").replace(
+            r#""dtstr": "revpi-example-2022""#,
+            &format!(r#""dtstr": "{dtstr}""#),
+        )
+    };
+    for dtstr in ["revpi-example-2022", "revpi_example.v2"] {
+        assert_eq!(parse_config(&config(dtstr)).unwrap().dtstr, dtstr);
+    }
+    assert!(matches!(
+        parse_config(&config("")),
+        Err(RevPiError::Validation(ValidationError::DtstrEmpty))
+    ));
+    assert!(matches!(
+        parse_config(&config("revpi example")),
+        Err(RevPiError::Validation(ValidationError::DtstrInvalidChar {
+            c: ' ',
+            ..
+        }))
+    ));
+    assert!(matches!(
+        parse_config(&config("revpi-example\\t")),
+        Err(RevPiError::Validation(ValidationError::DtstrInvalidChar {
+            c: '\t',
+            ..
+        }))
+    ));
+    assert_eq!(
+        ValidationError::DtstrInvalidChar {
+            dtstr: "revpi example".to_string(),
+            c: ' '
+        }
+        .to_string(),
+        "invalid value: `revpi example`: Invalid character in device tree string: ' ' \
+        (allowed: A-Z, a-z, 0-9, `.`, `_` and `-`)"
+    );
+}
+
+#[test]
+fn test_parse_yaml_config() {
+    let yaml = r#"
+version: 1
+eeprom_data_version: 3
+vstr: KUNBUS GmbH
+pstr: RevPi ExampleDevice 8GB
+pid: 666
+prev: 3
+pver: 333
+dtstr: revpi-example-2022
+# Comments are the reason to use YAML
+gpiobanks:
+  - drive: 8mA
+    gpios:
+      - { gpio: 2, fsel: input, pull: up }
+"#;
+    let eep = parse_config_files((yaml, ConfigFormat::Yaml), None, None).unwrap();
+    assert_eq!(eep.pid, 666);
+    assert_eq!(eep.gpiobanks.len(), 1);
+
+    // The banks can have another format than the config
+    let bank1 = r#"{ "drive": "16mA", "gpios": [ { "gpio": 31, "fsel": "output" } ] }"#;
+    let eep = parse_config_files(
+        (yaml, ConfigFormat::Yaml),
+        None,
+        Some((bank1, ConfigFormat::Json)),
+    )
+    .unwrap();
+    assert_eq!(eep.gpiobanks.len(), 2);
+
+    // Unknown fields are still rejected and errors carry the path
+    let e = parse_config_files(
+        (&yaml.replace("pull: up", "pul: up"), ConfigFormat::Yaml),
+        None,
+        None,
+    )
+    .unwrap_err();
+    assert!(
+        matches!(&e, RevPiError::Yaml { path, .. } if path == "gpiobanks[0].gpios[0].pul"),
+        "{e:?}"
+    );
+    assert!(
+        e.to_string()
+            .starts_with("gpiobanks[0].gpios[0]: unknown field `pul`"),
+        "{e}"
+    );
+}
+
+#[test]
+fn test_eeprom_data_version() {
+    let config = |version: u16| {
+        format!(
+            r#"{{
+                "version": 1,
+                "eeprom_data_version": {version},
+                "vstr": "KUNBUS GmbH",
+                "pstr": "RevPi ExampleDevice",
+                "pid": 666,
+                "prev": 3,
+                "pver": 333,
+                "dtstr": "revpi-example-2022",
+                "gpiobanks": [ {{ "drive": "default", "gpios": [] }} ]
+            }}"#
+        )
+    };
+    assert!(matches!(
+        parse_config(&config(0)),
+        Err(RevPiError::Validation(
+            ValidationError::InvalidEepromDataVersion(0)
+        ))
+    ));
+    assert_eq!(parse_config(&config(1)).unwrap().eeprom_data_version, 1);
+    // The version is independent for each product, so there is no upper limit
+    assert_eq!(
+        parse_config(&config(1000)).unwrap().eeprom_data_version,
+        1000
+    );
+}
+
+#[test]
+fn test_parse_config_with_gpio_banks() {
+    let config = r#"{
+        "version": 1,
+        "eeprom_data_version": 3,
+        "vstr": "KUNBUS GmbH",
+        "pstr": "RevPi ExampleDevice",
+        "pid": 666,
+        "prev": 3,
+        "pver": 333,
+        "dtstr": "revpi-example-2022"
+    }"#;
+    let bank0 = r#"{ "drive": "8mA", "gpios": [ { "gpio": 2, "fsel": "input" } ] }"#;
+    let bank1 = r#"{ "drive": "16mA", "gpios": [ { "gpio": 31, "fsel": "output" } ] }"#;
+
+    assert!(matches!(
+        parse_config(config),
+        Err(RevPiError::Validation(ValidationError::GpioBankCount(0)))
+    ));
+    assert!(matches!(
+        parse_config_with_gpio_banks(config, None, Some(bank1)),
+        Err(RevPiError::Validation(
+            ValidationError::GpioBank1WithoutBank0
+        ))
+    ));
+    assert!(matches!(
+        parse_config_with_gpio_banks(config, Some("{"), None),
+        Err(RevPiError::Json { .. })
+    ));
+    assert!(matches!(
+        parse_config(&config.replace("\"pid\": 666", "\"pid\": \"666\"")),
+        Err(RevPiError::Json { .. })
+    ));
+
+    let eep = parse_config_with_gpio_banks(config, Some(bank0), None).unwrap();
+    assert_eq!(eep.gpiobanks.len(), 1);
+    let eep = parse_config_with_gpio_banks(config, Some(bank0), Some(bank1)).unwrap();
+    assert_eq!(eep.gpiobanks.len(), 2);
+    assert_eq!(
+        serde_json::to_value(&eep.gpiobanks[1]).unwrap()["gpios"][0]["gpio"],
+        31
+    );
+
+    // The banks are validated after they are combined
+    assert!(matches!(
+        parse_config_with_gpio_banks(config, Some(bank1), None),
+        Err(RevPiError::Validation(ValidationError::GpioOutOfRange {
+            bank: gpio_map::GpioBank::Bank0,
+            gpio: 31
+        }))
+    ));
+    assert!(matches!(
+        parse_config_with_gpio_banks(config, Some(bank0), Some(bank0)),
+        Err(RevPiError::Validation(ValidationError::GpioInBothBanks(2)))
+    ));
+    assert!(matches!(
+        parse_config_with_gpio_banks(config, Some(bank1), Some(bank1)),
+        Err(RevPiError::Validation(ValidationError::GpioInBothBanks(31)))
+    ));
+    let bank1_dup = r#"{ "drive": "16mA", "gpios": [
+        { "gpio": 31, "fsel": "output" }, { "gpio": 31, "fsel": "input" } ] }"#;
+    assert!(matches!(
+        parse_config_with_gpio_banks(config, Some(bank0), Some(bank1_dup)),
+        Err(RevPiError::Validation(ValidationError::GpioDuplicate(31)))
+    ));
+
+    // Replace the banks of the configuration
+    let eep = parse_config_with_gpio_banks(
+        include_str!("../../docs/example.json"),
+        Some(bank0),
+        Some(bank1),
+    )
+    .unwrap();
+    assert_eq!(eep.gpiobanks.len(), 2);
+    assert_eq!(
+        serde_json::to_value(&eep.gpiobanks[0]).unwrap()["gpios"]
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(
+        serde_json::to_value(&eep.gpiobanks[1]).unwrap()["gpios"][0]["gpio"],
+        31
+    );
+}
+
+/// Check that `mac` can be the address of a single device
+///
+/// All zeros, the broadcast and multicast addresses are rejected. In `strict` mode locally
+/// administered addresses are rejected as well.
+pub fn validate_mac(mac: MacAddr6, strict: bool) -> Result<(), ValidationError> {
+    if mac.is_nil() {
+        return Err(ValidationError::MacNil);
+    }
+    if mac.is_broadcast() {
+        return Err(ValidationError::MacBroadcast);
+    }
+    if mac.is_multicast() {
+        return Err(ValidationError::MacMulticast(mac));
+    }
+    if strict && mac.is_local() {
+        return Err(ValidationError::MacLocal(mac));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_validate_mac() {
+    let mac = |s: &str| s.parse::<MacAddr6>().unwrap();
+    for strict in [false, true] {
+        assert_eq!(validate_mac(mac("c8:3e:a7:00:00:01"), strict), Ok(()));
+        assert_eq!(
+            validate_mac(mac("00:00:00:00:00:00"), strict),
+            Err(ValidationError::MacNil)
+        );
+        assert_eq!(
+            validate_mac(mac("ff:ff:ff:ff:ff:ff"), strict),
+            Err(ValidationError::MacBroadcast)
+        );
+        assert_eq!(
+            validate_mac(mac("01:00:5e:00:00:01"), strict),
+            Err(ValidationError::MacMulticast(mac("01:00:5e:00:00:01")))
+        );
+    }
+    assert_eq!(validate_mac(mac("02:00:00:00:00:01"), false), Ok(()));
+    assert_eq!(
+        validate_mac(mac("02:00:00:00:00:01"), true),
+        Err(ValidationError::MacLocal(mac("02:00:00:00:00:01")))
+    );
+
+    let mut config = test_config();
+    config.mac = Some(mac("ff:ff:ff:ff:ff:ff"));
+    assert_eq!(validate(&config), Err(ValidationError::MacBroadcast));
+}
+
+/// Return `mac` incremented by `n` or `None` if it would overflow `ff:ff:ff:ff:ff:ff`
+pub fn mac_add(mac: MacAddr6, n: u64) -> Option<MacAddr6> {
+    let mut bytes = [0u8; 8];
+    bytes[2..].copy_from_slice(mac.as_bytes());
+    let value = u64::from_be_bytes(bytes).checked_add(n)?;
+    if value > 0xffff_ffff_ffff {
+        return None;
+    }
+    let bytes: [u8; 6] = value.to_be_bytes()[2..]
+        .try_into()
+        .expect("BUG: A mac address has 6 bytes");
+    Some(MacAddr6::from(bytes))
+}
+
+#[test]
+fn test_mac_add() {
+    let mac = |s: &str| s.parse::<MacAddr6>().unwrap();
+    assert_eq!(
+        mac_add(mac("c8:3e:a7:00:00:01"), 0),
+        Some(mac("c8:3e:a7:00:00:01"))
+    );
+    assert_eq!(
+        mac_add(mac("c8:3e:a7:00:00:01"), 1),
+        Some(mac("c8:3e:a7:00:00:02"))
+    );
+    assert_eq!(
+        mac_add(mac("c8:3e:a7:00:00:ff"), 1),
+        Some(mac("c8:3e:a7:00:01:00"))
+    );
+    assert_eq!(
+        mac_add(mac("c8:3e:a7:ff:ff:ff"), 2),
+        Some(mac("c8:3e:a8:00:00:01"))
+    );
+    assert_eq!(
+        mac_add(mac("ff:ff:ff:ff:ff:fe"), 1),
+        Some(mac("ff:ff:ff:ff:ff:ff"))
+    );
+    assert_eq!(mac_add(mac("ff:ff:ff:ff:ff:fe"), 2), None);
+}
+
+/// Validate the block of `count` consecutive mac addresses starting at `mac`
+///
+/// Every address of the block must be valid (see [validate_mac()]).
+pub fn validate_mac_count(mac: MacAddr6, count: u8) -> Result<(), ValidationError> {
+    if count == 0 {
+        return Err(ValidationError::MacCountZero);
+    }
+    let last = mac_add(mac, u64::from(count) - 1)
+        .ok_or(ValidationError::MacCountOverflow { mac, count })?;
+    validate_mac(last, false).map_err(|_| ValidationError::MacCountOverflow { mac, count })
+}
+
+#[test]
+fn test_validate_mac_count() {
+    let mac = |s: &str| s.parse::<MacAddr6>().unwrap();
+    assert_eq!(validate_mac_count(mac("c8:3e:a7:00:00:01"), 1), Ok(()));
+    assert_eq!(validate_mac_count(mac("c8:3e:a7:00:00:01"), 255), Ok(()));
+    assert_eq!(
+        validate_mac_count(mac("c8:3e:a7:00:00:01"), 0),
+        Err(ValidationError::MacCountZero)
+    );
+    assert_eq!(
+        validate_mac_count(mac("ff:ff:ff:ff:ff:fd"), 3),
+        Err(ValidationError::MacCountOverflow {
+            mac: mac("ff:ff:ff:ff:ff:fd"),
+            count: 3
+        })
+    );
+
+    let mut config = test_config();
+    config.mac_count = Some(0);
+    assert_eq!(validate(&config), Err(ValidationError::MacCountZero));
+    config.mac_count = Some(4);
+    assert_eq!(validate(&config), Ok(()));
+    config.mac = Some(mac("fc:ff:ff:ff:ff:fe"));
+    assert!(matches!(
+        validate(&config),
+        Err(ValidationError::MacCountOverflow { count: 4, .. })
+    ));
+
+    // The mac count is written as custom_7 and read back
+    config.mac = Some(mac("c8:3e:a7:00:00:01"));
+    let eep = config.to_eep().unwrap();
+    assert_eq!(eep.atom_count(), 3 + 8 + 1);
+    assert_eq!(RevPiHatEeprom::from_eep(&eep).unwrap().mac_count, Some(4));
+    config.mac_count = None;
+    let eep = config.to_eep().unwrap();
+    assert_eq!(eep.atom_count(), 3 + 7 + 1);
+    assert_eq!(RevPiHatEeprom::from_eep(&eep).unwrap().mac_count, None);
+}
+
 fn validate(eep: &RevPiHatEeprom) -> Result<(), ValidationError> {
-    if eep.version != 1 {
-        return Err(ValidationError(format!(
-            "invalid value: `{}`: Unsupported format version",
-            eep.version
-        )));
+    if !SUPPORTED_FORMAT_VERSIONS.contains(&eep.version) {
+        return Err(ValidationError::UnsupportedFormatVersion(eep.version));
+    }
+    if !(EEPROM_DATA_VERSION_MIN..=EEPROM_DATA_VERSION_MAX).contains(&eep.eeprom_data_version) {
+        return Err(ValidationError::InvalidEepromDataVersion(
+            eep.eeprom_data_version,
+        ));
+    }
+    if !(PVER_MIN..=PVER_MAX).contains(&eep.pver) {
+        return Err(ValidationError::InvalidPver(eep.pver));
     }
     if eep.pstr.len() >= 256 {
-        return Err(ValidationError(format!(
-            "invalid value: `{}`: Product string to long {} (max: {}) bytes",
-            eep.pstr,
-            eep.pstr.len(),
-            u8::MAX
-        )));
+        return Err(ValidationError::PstrTooLong(eep.pstr.clone()));
     }
     if eep.vstr.len() >= 256 {
-        return Err(ValidationError(format!(
-            "invalid value: `{}`: Vendor string to long: {} (max: {}) bytes",
-            eep.vstr,
-            eep.vstr.len(),
-            u8::MAX
-        )));
+        return Err(ValidationError::VstrTooLong(eep.vstr.clone()));
     }
     if eep.dtstr.len() >= u32::MAX as usize {
-        return Err(ValidationError(format!(
-            "invalid value: `{}`: Device tree string to long: {} (max: {}) bytes",
-            eep.dtstr,
-            eep.dtstr.len(),
-            u32::MAX
-        )));
+        return Err(ValidationError::DtstrTooLong(eep.dtstr.clone()));
+    }
+    if eep.dtstr.is_empty() {
+        return Err(ValidationError::DtstrEmpty);
+    }
+    if let Some(c) = eep
+        .dtstr
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || ['.', '_', '-'].contains(c)))
+    {
+        return Err(ValidationError::DtstrInvalidChar {
+            dtstr: eep.dtstr.clone(),
+            c,
+        });
+    }
+    if let Some(mac) = eep.mac {
+        validate_mac(mac, false)?;
+    }
+    if let Some(count) = eep.mac_count {
+        if count == 0 {
+            return Err(ValidationError::MacCountZero);
+        }
+        if let Some(mac) = eep.mac {
+            validate_mac_count(mac, count)?;
+        }
     }
     if eep.gpiobanks.is_empty() || eep.gpiobanks.len() > 2 {
-        return Err(ValidationError(format!(
-            "unsupported number of gpio banks: {} (min: 1; max: 2)",
-            eep.gpiobanks.len()
-        )));
+        return Err(ValidationError::GpioBankCount(eep.gpiobanks.len()));
+    }
+    // Checked before the ranges of the banks, so a gpio in both banks is reported as such
+    let mut configured_gpios = [None; u8::MAX as usize + 1];
+    for (bank, gpiobank) in eep.gpiobanks.iter().enumerate() {
+        for gpio in gpiobank.gpio_numbers() {
+            match configured_gpios[gpio as usize] {
+                Some(other) if other != bank => return Err(ValidationError::GpioInBothBanks(gpio)),
+                _ => configured_gpios[gpio as usize] = Some(bank),
+            }
+        }
     }
     eep.gpiobanks[0].validate(gpio_map::GpioBank::Bank0)?;
     if eep.gpiobanks.len() > 1 {
@@ -0,0 +1,582 @@
+// SPDX-FileCopyrightText: 2022 KUNBUS GmbH <support@kunbus.com>
+//
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+//! The text configuration format of the Raspberry Pi `eepmake` tool
+//!
+//! This is the format of the `eeprom_settings.txt` template of the HAT `eepromutils`:
+//! ```text
+//! # comment
+//! product_uuid 00000000-0000-0000-0000-000000000000
+//! product_id 0x0001
+//! product_ver 0x0002
+//! vendor "ACME Technology Company"
+//! product "Special Sensor Board"
+//! gpio_drive 0
+//! gpio_slew 0
+//! gpio_hysteresis 0
+//! back_power 0
+//! setgpio 2 ALT0 DEFAULT
+//! custom_data
+//! deadbeef cafef00d
+//! end
+//! ```
+//! The Linux DT blob is not part of the text format; `eepmake` reads it from a separate file.
+
+use crate::gpio_map::{
+    self, GpioBackPower, GpioDrive, GpioFsel, GpioHysteresis, GpioPin, GpioPull, GpioSlew,
+};
+use crate::{
+    Eep, EepAtom, EepAtomCustomData, EepAtomData, EepAtomLinuxDTBData, EepAtomVendorData,
+    EepPushError, LinuxDTB,
+};
+
+/// Errors returned when reading a configuration in the `eepmake` text format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EepmakeError {
+    /// The keyword in line `line` is not known
+    UnknownKey { line: usize, key: String },
+    /// The value of the keyword in line `line` can't be parsed
+    InvalidValue {
+        line: usize,
+        key: String,
+        reason: String,
+    },
+    /// The `custom_data` block starting in line `line` is not closed by `end`
+    UnterminatedCustomData { line: usize },
+    /// A mandatory keyword is missing
+    MissingField(&'static str),
+    /// The Atoms can't be created from the configuration
+    InvalidConfig(String),
+}
+
+impl std::fmt::Display for EepmakeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EepmakeError::UnknownKey { line, key } => {
+                write!(f, "line {line}: unknown keyword `{key}'")
+            }
+            EepmakeError::InvalidValue { line, key, reason } => {
+                write!(f, "line {line}: invalid value of `{key}': {reason}")
+            }
+            EepmakeError::UnterminatedCustomData { line } => {
+                write!(f, "line {line}: `custom_data' without `end'")
+            }
+            EepmakeError::MissingField(key) => write!(f, "required field `{key}' missing"),
+            EepmakeError::InvalidConfig(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl std::error::Error for EepmakeError {}
+
+impl From<EepPushError> for EepmakeError {
+    fn from(e: EepPushError) -> Self {
+        EepmakeError::InvalidConfig(e.to_string())
+    }
+}
+
+/// A configuration in the `eepmake` text format
+///
+/// All fields are optional when the text is parsed; the mandatory ones are checked by
+/// [`EepmakeConfig::to_eep()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EepmakeConfig {
+    /// `product_uuid`; `eepmake` replaces the nil UUID by a random one
+    pub uuid: Option<uuid::Uuid>,
+    /// `product_id`
+    pub pid: Option<u16>,
+    /// `product_ver`
+    pub pver: Option<u16>,
+    /// `vendor`
+    pub vstr: Option<String>,
+    /// `product`
+    pub pstr: Option<String>,
+    /// `gpio_drive`
+    pub gpio_drive: Option<GpioDrive>,
+    /// `gpio_slew`
+    pub gpio_slew: Option<GpioSlew>,
+    /// `gpio_hysteresis`
+    pub gpio_hyst: Option<GpioHysteresis>,
+    /// `back_power`
+    pub back_power: Option<GpioBackPower>,
+    /// `setgpio` lines in the order of the text
+    pub gpios: Vec<(u8, GpioPin)>,
+    /// The Linux DT blob, it is not part of the text format
+    pub dtb: Option<LinuxDTB>,
+    /// `custom_data` blocks
+    pub custom: Vec<Vec<u8>>,
+}
+
+/// Return the value of a line: the text after the keyword
+fn line_value(line: &str) -> &str {
+    match line.find(|c: char| c.is_whitespace()) {
+        Some(idx) => line[idx..].trim(),
+        None => "",
+    }
+}
+
+fn parse_string(value: &str) -> String {
+    value
+        .trim_start_matches('"')
+        .trim_end_matches('"')
+        .to_string()
+}
+
+fn parse_dec_u8(value: &str) -> Result<u8, String> {
+    value.parse::<u8>().map_err(|e| e.to_string())
+}
+
+fn parse_hex_u16(value: &str) -> Result<u16, String> {
+    u16::from_str_radix(value.trim_start_matches("0x"), 16).map_err(|e| e.to_string())
+}
+
+fn parse_setting<T>(value: &str) -> Result<T, String>
+where
+    T: TryFrom<u8, Error = gpio_map::GpioReservedValue>,
+{
+    T::try_from(parse_dec_u8(value)?).map_err(|e| e.to_string())
+}
+
+/// The names of the gpio functions in `setgpio` lines
+const FSEL_NAMES: [(&str, GpioFsel); 8] = [
+    ("INPUT", GpioFsel::Input),
+    ("OUTPUT", GpioFsel::Output),
+    ("ALT0", GpioFsel::Alt0),
+    ("ALT1", GpioFsel::Alt1),
+    ("ALT2", GpioFsel::Alt2),
+    ("ALT3", GpioFsel::Alt3),
+    ("ALT4", GpioFsel::Alt4),
+    ("ALT5", GpioFsel::Alt5),
+];
+
+/// The names of the pull configurations in `setgpio` lines
+const PULL_NAMES: [(&str, GpioPull); 4] = [
+    ("DEFAULT", GpioPull::Default),
+    ("UP", GpioPull::Up),
+    ("DOWN", GpioPull::Down),
+    ("NONE", GpioPull::NoPull),
+];
+
+fn parse_name<T: Copy>(names: &[(&str, T)], s: &str) -> Option<T> {
+    names.iter().find(|(name, _)| *name == s).map(|(_, v)| *v)
+}
+
+fn name_of<T: PartialEq>(names: &[(&'static str, T)], v: T) -> &'static str {
+    // The tables cover every variant
+    names.iter().find(|(_, value)| *value == v).unwrap().0
+}
+
+/// Parse the arguments of a `setgpio` line: `<gpio> <fsel> <pull>`
+fn parse_setgpio(value: &str) -> Result<(u8, GpioPin), String> {
+    let chunks: Vec<&str> = value.split_ascii_whitespace().collect();
+    if chunks.len() != 3 {
+        return Err("expected `<gpio> <fsel> <pull>'".to_string());
+    }
+    let gpio = chunks[0]
+        .parse::<u8>()
+        .map_err(|e| format!("bad gpio pin number: {e}"))?;
+    let fsel = parse_name(&FSEL_NAMES, chunks[1]).ok_or(format!("bad function: {}", chunks[1]))?;
+    let pull = parse_name(&PULL_NAMES, chunks[2]).ok_or(format!("bad pull: {}", chunks[2]))?;
+    Ok((gpio, GpioPin::new(fsel, pull, true)))
+}
+
+impl EepmakeConfig {
+    /// Parse a configuration in the `eepmake` text format
+    ///
+    /// Empty lines and lines starting with `#` are ignored. The data of a `custom_data` block is
+    /// hex encoded, whitespace between the digits is ignored. Unknown keywords are rejected, so no
+    /// setting of a legacy configuration is dropped silently.
+    pub fn parse(s: &str) -> Result<EepmakeConfig, EepmakeError> {
+        let mut config = EepmakeConfig::default();
+        // The start line and the collected hex digits of an open custom_data block
+        let mut custom_data: Option<(usize, String)> = None;
+        for (n, line) in s.lines().enumerate() {
+            let n = n + 1;
+            let line = line.trim();
+            if line.starts_with('#') || line.is_empty() {
+                continue;
+            }
+            let key = line.split_whitespace().next().unwrap_or_default();
+            let value = line_value(line);
+            if let Some((start, mut data)) = custom_data.take() {
+                if key == "end" {
+                    let data = hex::decode(data).map_err(|e| EepmakeError::InvalidValue {
+                        line: start,
+                        key: "custom_data".to_string(),
+                        reason: e.to_string(),
+                    })?;
+                    config.custom.push(data);
+                } else {
+                    data.extend(line.chars().filter(|c| !c.is_ascii_whitespace()));
+                    custom_data = Some((start, data));
+                }
+                continue;
+            }
+            let invalid = |reason: String| EepmakeError::InvalidValue {
+                line: n,
+                key: key.to_string(),
+                reason,
+            };
+            match key {
+                "custom_data" => {
+                    let data = value.chars().filter(|c| !c.is_ascii_whitespace());
+                    custom_data = Some((n, data.collect()));
+                }
+                "product_uuid" => {
+                    config.uuid =
+                        Some(uuid::Uuid::parse_str(value).map_err(|e| invalid(e.to_string()))?)
+                }
+                "product_id" => config.pid = Some(parse_hex_u16(value).map_err(invalid)?),
+                "product_ver" => config.pver = Some(parse_hex_u16(value).map_err(invalid)?),
+                "vendor" => config.vstr = Some(parse_string(value)),
+                "product" => config.pstr = Some(parse_string(value)),
+                "gpio_drive" => config.gpio_drive = Some(parse_setting(value).map_err(invalid)?),
+                "gpio_slew" => config.gpio_slew = Some(parse_setting(value).map_err(invalid)?),
+                "gpio_hysteresis" => {
+                    config.gpio_hyst = Some(parse_setting(value).map_err(invalid)?)
+                }
+                "back_power" => config.back_power = Some(parse_setting(value).map_err(invalid)?),
+                "setgpio" => config.gpios.push(parse_setgpio(value).map_err(invalid)?),
+                _ => {
+                    return Err(EepmakeError::UnknownKey {
+                        line: n,
+                        key: key.to_string(),
+                    })
+                }
+            }
+        }
+        if let Some((line, _)) = custom_data {
+            return Err(EepmakeError::UnterminatedCustomData { line });
+        }
+        Ok(config)
+    }
+
+    /// Create the EEPROM from the configuration
+    ///
+    /// The `product_uuid`, `product_id`, `product_ver`, `vendor` and `product` are mandatory. A
+    /// missing gpio bank setting is left at its default. The Atoms are created in the order vendor
+    /// info, GPIO (bank 0) map, Linux DT blob and custom data.
+    pub fn to_eep(&self) -> Result<Eep, EepmakeError> {
+        let vendor = EepAtomVendorData::new(
+            self.uuid
+                .ok_or(EepmakeError::MissingField("product_uuid"))?,
+            self.pid.ok_or(EepmakeError::MissingField("product_id"))?,
+            self.pver.ok_or(EepmakeError::MissingField("product_ver"))?,
+            self.vstr
+                .clone()
+                .ok_or(EepmakeError::MissingField("vendor"))?,
+            self.pstr
+                .clone()
+                .ok_or(EepmakeError::MissingField("product"))?,
+        )
+        .map_err(|e| EepmakeError::InvalidConfig(e.to_string()))?;
+
+        let mut gpio_map = gpio_map::EepAtomGpioMapData::new(
+            gpio_map::GpioBank::Bank0,
+            self.gpio_drive.unwrap_or(GpioDrive::Default),
+            self.gpio_slew.unwrap_or(GpioSlew::Default),
+            self.gpio_hyst.unwrap_or(GpioHysteresis::Default),
+            self.back_power.unwrap_or(GpioBackPower::None),
+        );
+        for (gpio, pin) in &self.gpios {
+            gpio_map
+                .set(*gpio as usize, pin.clone())
+                .map_err(|e| EepmakeError::InvalidConfig(e.to_string()))?;
+        }
+
+        let mut eep = Eep::new(vendor, gpio_map);
+        if let Some(dtb) = &self.dtb {
+            eep.push(EepAtom::new_linux_dtb(EepAtomLinuxDTBData::new(
+                dtb.clone(),
+            )))?;
+        }
+        for data in &self.custom {
+            eep.push(EepAtom::new_custom(EepAtomCustomData::new(data.clone())))?;
+        }
+        Ok(eep)
+    }
+
+    /// Create the configuration from an EEPROM
+    ///
+    /// Only the used pins of the GPIO (bank 0) map are converted to `setgpio` lines, as `eepmake`
+    /// marks every configured pin as used. A GPIO (bank 1) map and Atoms of an unknown type can't
+    /// be expressed in the text format and are rejected.
+    pub fn from_eep(eep: &Eep) -> Result<EepmakeConfig, EepmakeError> {
+        let mut config = EepmakeConfig::default();
+        for atom in eep.atoms() {
+            match atom.data() {
+                EepAtomData::VendorInfo(vendor) => {
+                    config.uuid = Some(vendor.uuid());
+                    config.pid = Some(vendor.pid());
+                    config.pver = Some(vendor.pver());
+                    config.vstr = Some(vendor.vstr().to_string());
+                    config.pstr = Some(vendor.pstr().to_string());
+                }
+                EepAtomData::GpioBank0Map(gpio_map) => {
+                    config.gpio_drive = Some(gpio_map.drive());
+                    config.gpio_slew = Some(gpio_map.slew());
+                    config.gpio_hyst = Some(gpio_map.hysteresis());
+                    config.back_power = Some(gpio_map.back_power());
+                    config.gpios = (0u8..)
+                        .zip(gpio_map.gpios())
+                        .filter(|(_, pin)| pin.used())
+                        .map(|(gpio, pin)| (gpio, pin.clone()))
+                        .collect();
+                }
+                EepAtomData::LinuxDTB(dtb) => config.dtb = Some(dtb.data().clone()),
+                EepAtomData::ManufCustomData(data) => config.custom.push(data.data().to_vec()),
+                EepAtomData::GpioBank1Map(_) | EepAtomData::Unknown { .. } => {
+                    return Err(EepmakeError::InvalidConfig(format!(
+                        "The {} Atom can't be expressed in the eepmake format",
+                        atom.atype()
+                    )))
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Write the configuration in the `eepmake` text format
+///
+/// Missing fields are omitted. The Linux DT blob is not written. The data of the custom data
+/// blocks is written hex encoded with 16 bytes per line.
+impl std::fmt::Display for EepmakeConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(uuid) = self.uuid {
+            writeln!(f, "product_uuid {uuid}")?;
+        }
+        if let Some(pid) = self.pid {
+            writeln!(f, "product_id 0x{pid:04x}")?;
+        }
+        if let Some(pver) = self.pver {
+            writeln!(f, "product_ver 0x{pver:04x}")?;
+        }
+        if let Some(vstr) = &self.vstr {
+            writeln!(f, "vendor \"{vstr}\"")?;
+        }
+        if let Some(pstr) = &self.pstr {
+            writeln!(f, "product \"{pstr}\"")?;
+        }
+        if let Some(drive) = self.gpio_drive {
+            writeln!(f, "gpio_drive {}", drive as u8)?;
+        }
+        if let Some(slew) = self.gpio_slew {
+            writeln!(f, "gpio_slew {}", slew as u8)?;
+        }
+        if let Some(hyst) = self.gpio_hyst {
+            writeln!(f, "gpio_hysteresis {}", hyst as u8)?;
+        }
+        if let Some(power) = self.back_power {
+            writeln!(f, "back_power {}", power as u8)?;
+        }
+        for (gpio, pin) in &self.gpios {
+            writeln!(
+                f,
+                "setgpio {gpio} {} {}",
+                name_of(&FSEL_NAMES, pin.fsel()),
+                name_of(&PULL_NAMES, pin.pull())
+            )?;
+        }
+        for data in &self.custom {
+            writeln!(f, "custom_data")?;
+            for chunk in data.chunks(16) {
+                writeln!(f, "{}", hex::encode(chunk))?;
+            }
+            writeln!(f, "end")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+const TEST_CONFIG: &str = r#"
+# Legacy configuration of a HAT
+product_uuid 67e55044-10b1-426f-9247-bb680e5fe0c8
+product_id 0x007b
+product_ver 0x0003
+vendor "ACME Technology Company"
+product "Special Sensor Board"
+
+gpio_drive 4
+gpio_slew 1
+gpio_hysteresis 2
+back_power 1
+
+setgpio 2 ALT0 DEFAULT
+setgpio 3 ALT0 UP
+setgpio 27 OUTPUT NONE
+
+custom_data
+deadbeef cafe
+f00d
+end
+custom_data 0102
+end
+"#;
+
+#[test]
+fn test_parse() {
+    let config = EepmakeConfig::parse(TEST_CONFIG).unwrap();
+    assert_eq!(
+        config.uuid,
+        Some(uuid::uuid!("67e55044-10b1-426f-9247-bb680e5fe0c8"))
+    );
+    assert_eq!(config.pid, Some(0x7b));
+    assert_eq!(config.pver, Some(3));
+    assert_eq!(config.vstr.as_deref(), Some("ACME Technology Company"));
+    assert_eq!(config.pstr.as_deref(), Some("Special Sensor Board"));
+    assert_eq!(config.gpio_drive, Some(GpioDrive::Drive8mA));
+    assert_eq!(config.gpio_slew, Some(GpioSlew::RateLimiting));
+    assert_eq!(config.gpio_hyst, Some(GpioHysteresis::Enable));
+    assert_eq!(config.back_power, Some(GpioBackPower::BackPower1A3));
+    assert_eq!(
+        config.gpios,
+        vec![
+            (2, GpioPin::new(GpioFsel::Alt0, GpioPull::Default, true)),
+            (3, GpioPin::new(GpioFsel::Alt0, GpioPull::Up, true)),
+            (27, GpioPin::new(GpioFsel::Output, GpioPull::NoPull, true)),
+        ]
+    );
+    assert_eq!(
+        config.custom,
+        vec![
+            vec![0xde, 0xad, 0xbe, 0xef, 0xca, 0xfe, 0xf0, 0x0d],
+            vec![0x01, 0x02]
+        ]
+    );
+
+    let eep = config.to_eep().unwrap();
+    assert_eq!(eep, test_eep_custom());
+}
+
+#[cfg(test)]
+fn test_eep_custom() -> Eep {
+    let vendor = EepAtomVendorData::new(
+        uuid::uuid!("67e55044-10b1-426f-9247-bb680e5fe0c8"),
+        0x7b,
+        3,
+        "ACME Technology Company".to_string(),
+        "Special Sensor Board".to_string(),
+    )
+    .unwrap();
+    let mut gpio_map = gpio_map::EepAtomGpioMapData::new(
+        gpio_map::GpioBank::Bank0,
+        GpioDrive::Drive8mA,
+        GpioSlew::RateLimiting,
+        GpioHysteresis::Enable,
+        GpioBackPower::BackPower1A3,
+    );
+    gpio_map
+        .set(2, GpioPin::new(GpioFsel::Alt0, GpioPull::Default, true))
+        .unwrap();
+    gpio_map
+        .set(3, GpioPin::new(GpioFsel::Alt0, GpioPull::Up, true))
+        .unwrap();
+    gpio_map
+        .set(27, GpioPin::new(GpioFsel::Output, GpioPull::NoPull, true))
+        .unwrap();
+    let mut eep = Eep::new(vendor, gpio_map);
+    for data in [
+        vec![0xde, 0xad, 0xbe, 0xef, 0xca, 0xfe, 0xf0, 0x0d],
+        vec![0x01, 0x02],
+    ] {
+        eep.push(EepAtom::new_custom(EepAtomCustomData::new(data)))
+            .unwrap();
+    }
+    eep
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(
+        EepmakeConfig::parse("vendor \"ACME\"\nproduct_serial 1"),
+        Err(EepmakeError::UnknownKey {
+            line: 2,
+            key: "product_serial".to_string()
+        })
+    );
+    assert_eq!(
+        EepmakeConfig::parse("gpio_drive 9")
+            .unwrap_err()
+            .to_string(),
+        "line 1: invalid value of `gpio_drive': reserved drive value: 9"
+    );
+    assert!(matches!(
+        EepmakeConfig::parse("setgpio 2 ALT6 DEFAULT"),
+        Err(EepmakeError::InvalidValue { line: 1, .. })
+    ));
+    assert!(matches!(
+        EepmakeConfig::parse("custom_data\nabc\nend"),
+        Err(EepmakeError::InvalidValue { line: 1, .. })
+    ));
+    assert_eq!(
+        EepmakeConfig::parse("\ncustom_data\ndeadbeef"),
+        Err(EepmakeError::UnterminatedCustomData { line: 2 })
+    );
+
+    let mut config = EepmakeConfig::parse(TEST_CONFIG).unwrap();
+    config.pver = None;
+    assert_eq!(
+        config.to_eep(),
+        Err(EepmakeError::MissingField("product_ver"))
+    );
+    let mut config = EepmakeConfig::parse(TEST_CONFIG).unwrap();
+    config
+        .gpios
+        .push((28, GpioPin::new(GpioFsel::Input, GpioPull::Default, true)));
+    assert!(matches!(
+        config.to_eep(),
+        Err(EepmakeError::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_export() {
+    let config = EepmakeConfig::parse(TEST_CONFIG).unwrap();
+    let exported = EepmakeConfig::from_eep(&config.to_eep().unwrap()).unwrap();
+    assert_eq!(exported, config);
+    let text = exported.to_string();
+    assert_eq!(
+        text,
+        "product_uuid 67e55044-10b1-426f-9247-bb680e5fe0c8\n\
+        product_id 0x007b\n\
+        product_ver 0x0003\n\
+        vendor \"ACME Technology Company\"\n\
+        product \"Special Sensor Board\"\n\
+        gpio_drive 4\n\
+        gpio_slew 1\n\
+        gpio_hysteresis 2\n\
+        back_power 1\n\
+        setgpio 2 ALT0 DEFAULT\n\
+        setgpio 3 ALT0 UP\n\
+        setgpio 27 OUTPUT NONE\n\
+        custom_data\n\
+        deadbeefcafef00d\n\
+        end\n\
+        custom_data\n\
+        0102\n\
+        end\n"
+    );
+    // import-then-export is stable
+    let reimported = EepmakeConfig::parse(&text).unwrap();
+    assert_eq!(reimported, config);
+    assert_eq!(reimported.to_string(), text);
+
+    let mut eep = config.to_eep().unwrap();
+    eep.push(EepAtom::new_gpio_bank1_map(
+        gpio_map::EepAtomGpioMapData::new(
+            gpio_map::GpioBank::Bank1,
+            GpioDrive::Default,
+            GpioSlew::Default,
+            GpioHysteresis::Default,
+            GpioBackPower::None,
+        ),
+    ))
+    .unwrap();
+    assert!(matches!(
+        EepmakeConfig::from_eep(&eep),
+        Err(EepmakeError::InvalidConfig(_))
+    ));
+}
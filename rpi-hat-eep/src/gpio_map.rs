@@ -37,6 +37,24 @@ impl std::fmt::Display for GpioError {
     }
 }
 
+/// A reserved value of a gpio bank setting
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GpioReservedValue {
+    /// The name of the setting (`drive`, `slew`, `hysteresis`, `back_power`, `fsel`, `pull` or
+    /// `gpio` for the reserved bits of a gpio pin byte)
+    pub field: &'static str,
+    /// The reserved value
+    pub value: u8,
+}
+
+impl std::error::Error for GpioReservedValue {}
+
+impl std::fmt::Display for GpioReservedValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "reserved {} value: {}", self.field, self.value)
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum GpioBank {
     Bank0,
@@ -54,7 +72,7 @@ impl std::fmt::Display for GpioBank {
 }
 
 /// 0=leave at default, 1-8=drive*2mA, 9-15=reserved
-#[derive(Clone, Copy, Debug, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
 pub enum GpioDrive {
     Default = 0,
     Drive2mA = 1,
@@ -67,8 +85,19 @@ pub enum GpioDrive {
     Drive16mA = 8,
 }
 
+impl TryFrom<u8> for GpioDrive {
+    type Error = GpioReservedValue;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u8(value).ok_or(GpioReservedValue {
+            field: "drive",
+            value,
+        })
+    }
+}
+
 /// 0=leave at default, 1=slew rate limiting, 2=no slew limiting, 3=reserved
-#[derive(Clone, Copy, Debug, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
 pub enum GpioSlew {
     /// leave at default
     Default = 0,
@@ -78,8 +107,19 @@ pub enum GpioSlew {
     NoLimit = 2,
 }
 
+impl TryFrom<u8> for GpioSlew {
+    type Error = GpioReservedValue;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u8(value).ok_or(GpioReservedValue {
+            field: "slew",
+            value,
+        })
+    }
+}
+
 /// 0=leave at default, 1=hysteresis disabled, 2=hysteresis enabled, 3=reserved
-#[derive(Clone, Copy, Debug, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
 pub enum GpioHysteresis {
     /// leave at default
     Default = 0,
@@ -88,6 +128,17 @@ pub enum GpioHysteresis {
     /// hysteresis enabled
     Enable = 2,
 }
+
+impl TryFrom<u8> for GpioHysteresis {
+    type Error = GpioReservedValue;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u8(value).ok_or(GpioReservedValue {
+            field: "hysteresis",
+            value,
+        })
+    }
+}
 /// defines if the board backpowers the Pi
 ///
 /// ```text
@@ -97,7 +148,7 @@ pub enum GpioHysteresis {
 /// 3=reserved
 /// If back_power=2 high current USB mode is automatically enabled.
 /// ```
-#[derive(Clone, Copy, Debug, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
 pub enum GpioBackPower {
     /// board does not back power Pi
     None = 0,
@@ -107,6 +158,17 @@ pub enum GpioBackPower {
     BackPower2A = 2,
 }
 
+impl TryFrom<u8> for GpioBackPower {
+    type Error = GpioReservedValue;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u8(value).ok_or(GpioReservedValue {
+            field: "back_power",
+            value,
+        })
+    }
+}
+
 /// GPIO function as per FSEL GPIO register field in BCM2835 datasheet
 ///
 /// ```text
@@ -120,7 +182,7 @@ pub enum GpioBackPower {
 /// 011 = GPIO Pin n takes alternate function 4
 /// 010 = GPIO Pin n takes alternate function 5
 /// ```
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, FromPrimitive)]
 pub enum GpioFsel {
     /// GPIO Pin is an input
     #[default]
@@ -141,8 +203,114 @@ pub enum GpioFsel {
     Alt5 = 2,
 }
 
+impl TryFrom<u8> for GpioFsel {
+    type Error = GpioReservedValue;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u8(value).ok_or(GpioReservedValue {
+            field: "fsel",
+            value,
+        })
+    }
+}
+
+/// The alternate functions ALT0...ALT5 of the GPIOs of bank 0
+///
+/// The names are taken from the table "GPIO Alternate function select register" of the BCM2835 ARM
+/// Peripherals datasheet. Reserved and undocumented functions are `None`.
+#[rustfmt::skip]
+const BANK0_ALT_FUNCTIONS: [[Option<&str>; 6]; BANK0_GPIOS] = [
+    // GPIO 0
+    [Some("SDA0"), Some("SA5"), None, None, None, None],
+    // GPIO 1
+    [Some("SCL0"), Some("SA4"), None, None, None, None],
+    // GPIO 2
+    [Some("SDA1"), Some("SA3"), None, None, None, None],
+    // GPIO 3
+    [Some("SCL1"), Some("SA2"), None, None, None, None],
+    // GPIO 4
+    [Some("GPCLK0"), Some("SA1"), None, None, None, Some("ARM_TDI")],
+    // GPIO 5
+    [Some("GPCLK1"), Some("SA0"), None, None, None, Some("ARM_TDO")],
+    // GPIO 6
+    [Some("GPCLK2"), Some("SOE_N/SE"), None, None, None, Some("ARM_RTCK")],
+    // GPIO 7
+    [Some("SPI0_CE1_N"), Some("SWE_N/SRW_N"), None, None, None, None],
+    // GPIO 8
+    [Some("SPI0_CE0_N"), Some("SD0"), None, None, None, None],
+    // GPIO 9
+    [Some("SPI0_MISO"), Some("SD1"), None, None, None, None],
+    // GPIO 10
+    [Some("SPI0_MOSI"), Some("SD2"), None, None, None, None],
+    // GPIO 11
+    [Some("SPI0_SCLK"), Some("SD3"), None, None, None, None],
+    // GPIO 12
+    [Some("PWM0"), Some("SD4"), None, None, None, Some("ARM_TMS")],
+    // GPIO 13
+    [Some("PWM1"), Some("SD5"), None, None, None, Some("ARM_TCK")],
+    // GPIO 14
+    [Some("TXD0"), Some("SD6"), None, None, None, Some("TXD1")],
+    // GPIO 15
+    [Some("RXD0"), Some("SD7"), None, None, None, Some("RXD1")],
+    // GPIO 16
+    [None, Some("SD8"), None, Some("CTS0"), Some("SPI1_CE2_N"), Some("CTS1")],
+    // GPIO 17
+    [None, Some("SD9"), None, Some("RTS0"), Some("SPI1_CE1_N"), Some("RTS1")],
+    // GPIO 18
+    [Some("PCM_CLK"), Some("SD10"), None, Some("BSCSL_SDA/MOSI"), Some("SPI1_CE0_N"), Some("PWM0")],
+    // GPIO 19
+    [Some("PCM_FS"), Some("SD11"), None, Some("BSCSL_SCL/SCLK"), Some("SPI1_MISO"), Some("PWM1")],
+    // GPIO 20
+    [Some("PCM_DIN"), Some("SD12"), None, Some("BSCSL/MISO"), Some("SPI1_MOSI"), Some("GPCLK0")],
+    // GPIO 21
+    [Some("PCM_DOUT"), Some("SD13"), None, Some("BSCSL/CE"), Some("SPI1_SCLK"), Some("GPCLK1")],
+    // GPIO 22
+    [None, Some("SD14"), None, Some("SD1_CLK"), Some("ARM_TRST"), None],
+    // GPIO 23
+    [None, Some("SD15"), None, Some("SD1_CMD"), Some("ARM_RTCK"), None],
+    // GPIO 24
+    [None, Some("SD16"), None, Some("SD1_DAT0"), Some("ARM_TDO"), None],
+    // GPIO 25
+    [None, Some("SD17"), None, Some("SD1_DAT1"), Some("ARM_TCK"), None],
+    // GPIO 26
+    [None, None, None, Some("SD1_DAT2"), Some("ARM_TDI"), None],
+    // GPIO 27
+    [None, None, None, Some("SD1_DAT3"), Some("ARM_TMS"), None],
+];
+
+/// Return the name of the alternate function of a GPIO of bank 0, e.g. `SDA1` for GPIO 2 and
+/// [`GpioFsel::Alt0`]
+///
+/// `None` is returned for GPIOs outside of bank 0, for [`GpioFsel::Input`] and
+/// [`GpioFsel::Output`] and for reserved or undocumented alternate functions.
+pub fn alt_function_name(gpio: u8, fsel: GpioFsel) -> Option<&'static str> {
+    let alt = match fsel {
+        GpioFsel::Input | GpioFsel::Output => return None,
+        GpioFsel::Alt0 => 0,
+        GpioFsel::Alt1 => 1,
+        GpioFsel::Alt2 => 2,
+        GpioFsel::Alt3 => 3,
+        GpioFsel::Alt4 => 4,
+        GpioFsel::Alt5 => 5,
+    };
+    BANK0_ALT_FUNCTIONS.get(gpio as usize)?[alt]
+}
+
+#[test]
+fn test_alt_function_name() {
+    assert_eq!(alt_function_name(2, GpioFsel::Alt0), Some("SDA1"));
+    assert_eq!(alt_function_name(3, GpioFsel::Alt0), Some("SCL1"));
+    assert_eq!(alt_function_name(14, GpioFsel::Alt0), Some("TXD0"));
+    assert_eq!(alt_function_name(15, GpioFsel::Alt5), Some("RXD1"));
+    assert_eq!(alt_function_name(27, GpioFsel::Alt4), Some("ARM_TMS"));
+    assert_eq!(alt_function_name(2, GpioFsel::Input), None);
+    assert_eq!(alt_function_name(2, GpioFsel::Output), None);
+    assert_eq!(alt_function_name(2, GpioFsel::Alt2), None);
+    assert_eq!(alt_function_name(28, GpioFsel::Alt0), None);
+}
+
 /// 0=leave at default setting,  1=pullup, 2=pulldown, 3=no pull
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, FromPrimitive)]
 pub enum GpioPull {
     /// leave at default setting
     #[default]
@@ -155,7 +323,18 @@ pub enum GpioPull {
     NoPull = 3,
 }
 
-#[derive(Debug, Default, Clone)]
+impl TryFrom<u8> for GpioPull {
+    type Error = GpioReservedValue;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u8(value).ok_or(GpioReservedValue {
+            field: "pull",
+            value,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct GpioPin {
     fsel: GpioFsel,
     pull: GpioPull,
@@ -167,11 +346,41 @@ impl GpioPin {
         GpioPin { fsel, pull, used }
     }
 
+    /// Return the function of the pin
+    pub fn fsel(&self) -> GpioFsel {
+        self.fsel
+    }
+
+    /// Return the pull configuration of the pin
+    pub fn pull(&self) -> GpioPull {
+        self.pull
+    }
+
+    /// Return `true` if the board uses this pin
+    pub fn used(&self) -> bool {
+        self.used
+    }
+
     fn to_u8(&self) -> u8 {
         let fsel = self.fsel as u8;
         let pull = self.pull as u8;
         (fsel & 0x07) | (pull & 0x03) << 5 | (self.used as u8) << 7
     }
+
+    /// Decode a gpio pin byte of a GPIO map atom
+    ///
+    /// The reserved bits 3 and 4 must not be set.
+    pub fn from_u8(b: u8) -> Result<GpioPin, GpioReservedValue> {
+        if b & 0x18 != 0 {
+            return Err(GpioReservedValue {
+                field: "gpio",
+                value: b,
+            });
+        }
+        let fsel = GpioFsel::try_from(b & 0x07)?;
+        let pull = GpioPull::try_from((b >> 5) & 0x03)?;
+        Ok(GpioPin::new(fsel, pull, b & 0x80 != 0))
+    }
 }
 
 #[test]
@@ -191,6 +400,44 @@ fn test_gpio_pin() {
     assert_eq!(GpioPin::new(GpioFsel::Alt3, GpioPull::NoPull, true).to_u8(), 0xe7_u8);
 }
 
+#[test]
+fn test_gpio_pin_from_u8() {
+    for (b, fsel, pull, used) in [
+        (0x00, GpioFsel::Input, GpioPull::Default, false),
+        (0x01, GpioFsel::Output, GpioPull::Default, false),
+        (0x04, GpioFsel::Alt0, GpioPull::Default, false),
+        (0x05, GpioFsel::Alt1, GpioPull::Default, false),
+        (0x06, GpioFsel::Alt2, GpioPull::Default, false),
+        (0x07, GpioFsel::Alt3, GpioPull::Default, false),
+        (0x03, GpioFsel::Alt4, GpioPull::Default, false),
+        (0x02, GpioFsel::Alt5, GpioPull::Default, false),
+        (0x20, GpioFsel::Input, GpioPull::Up, false),
+        (0x40, GpioFsel::Input, GpioPull::Down, false),
+        (0x60, GpioFsel::Input, GpioPull::NoPull, false),
+        (0x80, GpioFsel::Input, GpioPull::Default, true),
+        (0xe7, GpioFsel::Alt3, GpioPull::NoPull, true),
+    ] {
+        assert_eq!(GpioPin::from_u8(b), Ok(GpioPin::new(fsel, pull, used)));
+    }
+    assert_eq!(
+        GpioFsel::try_from(8).unwrap_err().to_string(),
+        "reserved fsel value: 8"
+    );
+    assert_eq!(
+        GpioPull::try_from(4).unwrap_err().to_string(),
+        "reserved pull value: 4"
+    );
+    for b in [0x08, 0x10, 0x98] {
+        assert_eq!(
+            GpioPin::from_u8(b),
+            Err(GpioReservedValue {
+                field: "gpio",
+                value: b
+            })
+        );
+    }
+}
+
 /// This struct implements the GPIO map Atom
 ///
 /// [GPIO map atom data](https://github.com/raspberrypi/hats/blob/9616b5cd2bdf3e1d2d0330611387d639c1916100/eeprom-format.md#gpio-map-atom-data-type0x0002):
@@ -215,7 +462,7 @@ fn test_gpio_pin() {
 ///           [6:5] pulltype    0=leave at default setting,  1=pullup, 2=pulldown, 3=no pull
 ///           [  7] is_used     1=board uses this pin, 0=not connected and therefore not used
 /// ```
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EepAtomGpioMapData {
     bank: GpioBank,
     drive: GpioDrive,
@@ -271,6 +518,69 @@ impl EepAtomGpioMapData {
         self.gpios[n] = gpio;
         Ok(())
     }
+
+    /// Return the gpio bank of the map
+    pub fn bank(&self) -> GpioBank {
+        self.bank
+    }
+
+    /// Return the drive strength of the bank
+    pub fn drive(&self) -> GpioDrive {
+        self.drive
+    }
+
+    /// Return the slew rate of the bank
+    pub fn slew(&self) -> GpioSlew {
+        self.slew
+    }
+
+    /// Return the hysteresis of the bank
+    pub fn hysteresis(&self) -> GpioHysteresis {
+        self.hysteresis
+    }
+
+    /// Return the back power configuration
+    pub fn back_power(&self) -> GpioBackPower {
+        self.back_power
+    }
+
+    /// Return the pins of the bank
+    ///
+    /// The first pin of bank 1 is gpio 28 (see [`BANK0_GPIOS`]).
+    pub fn gpios(&self) -> &[GpioPin] {
+        &self.gpios
+    }
+}
+
+impl EepAtomGpioMapData {
+    /// Decode the data of a GPIO map atom as written by [`ToBytes::to_bytes()`]
+    ///
+    /// The data must contain exactly the bank_drive byte, the power byte and one byte for every gpio
+    /// of the bank. Reserved values are rejected.
+    pub(crate) fn from_bytes(bank: GpioBank, data: &[u8]) -> Result<EepAtomGpioMapData, String> {
+        let gpios = match bank {
+            GpioBank::Bank0 => BANK0_GPIOS,
+            GpioBank::Bank1 => BANK1_GPIOS,
+        };
+        if data.len() != 2 + gpios {
+            return Err(format!(
+                "invalid length of gpio map ({bank}): {} (expected: {} bytes)",
+                data.len(),
+                2 + gpios
+            ));
+        }
+        let bank_drive = data[0];
+        let drive = GpioDrive::try_from(bank_drive & 0x0f).map_err(|e| e.to_string())?;
+        let slew = GpioSlew::try_from((bank_drive >> 4) & 0x03).map_err(|e| e.to_string())?;
+        let hysteresis =
+            GpioHysteresis::try_from((bank_drive >> 6) & 0x03).map_err(|e| e.to_string())?;
+        let back_power = GpioBackPower::try_from(data[1]).map_err(|e| e.to_string())?;
+        let mut gpio_map = EepAtomGpioMapData::new(bank, drive, slew, hysteresis, back_power);
+        for (n, &b) in data[2..].iter().enumerate() {
+            gpio_map.gpios[n] = GpioPin::from_u8(b).map_err(|e| format!("gpio {n}: {e}"))?;
+        }
+        Ok(gpio_map)
+    }
 }
 
 impl ToBytes for EepAtomGpioMapData {
@@ -355,3 +665,101 @@ fn test_eep_atom_gpio_map() {
         })
     );
 }
+
+#[test]
+fn test_eep_atom_gpio_map_bank_drive() {
+    /*
+     * bank_drive: [3:0] drive, [5:4] slew, [7:6] hysteresis
+     * power: [1:0] back_power
+     */
+    #[rustfmt::skip]
+    let cases = [
+        (GpioDrive::Drive8mA, GpioSlew::RateLimiting, GpioHysteresis::Enable, GpioBackPower::None, 0x94, 0x00),
+        (GpioDrive::Drive16mA, GpioSlew::NoLimit, GpioHysteresis::Disable, GpioBackPower::BackPower1A3, 0x68, 0x01),
+        (GpioDrive::Drive2mA, GpioSlew::Default, GpioHysteresis::Default, GpioBackPower::BackPower2A, 0x01, 0x02),
+        (GpioDrive::Default, GpioSlew::NoLimit, GpioHysteresis::Enable, GpioBackPower::None, 0xa0, 0x00),
+        (GpioDrive::Drive14mA, GpioSlew::RateLimiting, GpioHysteresis::Disable, GpioBackPower::None, 0x57, 0x00),
+    ];
+    for (drive, slew, hysteresis, back_power, bank_drive, power) in cases {
+        let gpio_map =
+            EepAtomGpioMapData::new(GpioBank::Bank0, drive, slew, hysteresis, back_power);
+        let mut buf: Vec<u8> = Vec::new();
+        gpio_map.to_bytes(&mut buf);
+        assert_eq!(buf[0], bank_drive, "{drive:?} {slew:?} {hysteresis:?}");
+        assert_eq!(buf[1], power, "{back_power:?}");
+    }
+}
+
+#[test]
+fn test_gpio_reserved_values() {
+    assert_eq!(GpioDrive::try_from(8), Ok(GpioDrive::Drive16mA));
+    for value in 9..=15 {
+        assert_eq!(
+            GpioDrive::try_from(value),
+            Err(GpioReservedValue {
+                field: "drive",
+                value
+            })
+        );
+    }
+    assert_eq!(GpioSlew::try_from(2), Ok(GpioSlew::NoLimit));
+    assert_eq!(
+        GpioSlew::try_from(3).unwrap_err().to_string(),
+        "reserved slew value: 3"
+    );
+    assert_eq!(GpioHysteresis::try_from(2), Ok(GpioHysteresis::Enable));
+    assert_eq!(
+        GpioHysteresis::try_from(3).unwrap_err().to_string(),
+        "reserved hysteresis value: 3"
+    );
+    assert_eq!(GpioBackPower::try_from(2), Ok(GpioBackPower::BackPower2A));
+    assert_eq!(
+        GpioBackPower::try_from(3).unwrap_err().to_string(),
+        "reserved back_power value: 3"
+    );
+}
+
+#[test]
+fn test_eep_atom_gpio_map_from_bytes() {
+    let mut gpio_map = EepAtomGpioMapData::new(
+        GpioBank::Bank1,
+        GpioDrive::Drive12mA,
+        GpioSlew::NoLimit,
+        GpioHysteresis::Enable,
+        GpioBackPower::BackPower2A,
+    );
+    gpio_map
+        .set(31, GpioPin::new(GpioFsel::Alt3, GpioPull::NoPull, true))
+        .unwrap();
+    gpio_map
+        .set(45, GpioPin::new(GpioFsel::Alt4, GpioPull::Down, true))
+        .unwrap();
+    let mut buf: Vec<u8> = Vec::new();
+    gpio_map.to_bytes(&mut buf);
+
+    let decoded = EepAtomGpioMapData::from_bytes(GpioBank::Bank1, &buf).unwrap();
+    let mut decoded_buf: Vec<u8> = Vec::new();
+    decoded.to_bytes(&mut decoded_buf);
+    assert_eq!(buf, decoded_buf);
+
+    // wrong length
+    assert!(EepAtomGpioMapData::from_bytes(GpioBank::Bank0, &buf).is_err());
+    // reserved drive, slew, hysteresis and back_power values
+    for (i, b, e) in [
+        (0, 0x09, "reserved drive value: 9"),
+        (0, 0x30, "reserved slew value: 3"),
+        (0, 0xc0, "reserved hysteresis value: 3"),
+        (1, 0x03, "reserved back_power value: 3"),
+    ] {
+        let mut bad = buf.clone();
+        bad[i] = b;
+        assert_eq!(
+            EepAtomGpioMapData::from_bytes(GpioBank::Bank1, &bad).unwrap_err(),
+            e
+        );
+    }
+    // reserved bits of a gpio pin
+    let mut bad = buf.clone();
+    bad[2] = 0x08;
+    assert!(EepAtomGpioMapData::from_bytes(GpioBank::Bank1, &bad).is_err());
+}
@@ -3,13 +3,13 @@
 // SPDX-License-Identifier: GPL-2.0-or-later
 
 use crc::{Crc, CRC_16_ARC};
+use std::io::{self, Write};
 
 use self::gpio_map::EepAtomGpioMapData;
 
+pub mod eepmake;
 pub mod gpio_map;
 
-#[allow(clippy::len_without_is_empty)]
-
 /// This trait is used to write the object into a byte vector
 ///
 /// All objects which implement this trait can be written to a Vec<u8>. How the object is written to
@@ -18,6 +18,9 @@ pub mod gpio_map;
 /// * The [len](ToBytes::len()) method returns the size the object will use when it is written into
 ///   the vector.
 /// * The [to_bytes](ToBytes::to_bytes()) appends the object to a [Vec<u8>].
+///
+/// The object can also be written to any [Write] with [to_writer](ToBytes::to_writer()).
+#[allow(clippy::len_without_is_empty)]
 pub trait ToBytes {
     /// Return the size the object will use when it is written into the vector.
     ///
@@ -28,14 +31,55 @@ pub trait ToBytes {
     /// The function appends the object to a given vector. The size of the vector will be increased
     /// by [ToBytes::len()] bytes.
     fn to_bytes(&self, buf: &mut Vec<u8>);
+    /// This method writes the object to a given writer.
+    ///
+    /// The default implementation writes the vector created by [ToBytes::to_bytes()]. Objects
+    /// with large parts (e.g. the [Eep]) override it to avoid creating the whole vector.
+    fn to_writer<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(self.len());
+        self.to_bytes(&mut buf);
+        w.write_all(&buf)
+    }
 }
 
-#[derive(Debug)]
-pub struct EepError(String);
+/// Errors returned when creating Atoms
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EepError {
+    /// The atom type is invalid (0x0000 or 0xffff)
+    InvalidAtomType(u16),
+    /// The atom type is reserved (0x0006-0xfffe)
+    ReservedAtomType(u16),
+    /// The atom type is not a reserved type (see [`EepAtom::new_unknown()`])
+    NotReservedAtomType(u16),
+    /// The vendor string is longer than 255 bytes
+    VendorStringTooLong(usize),
+    /// The product string is longer than 255 bytes
+    ProductStringTooLong(usize),
+    /// The data of the Atom with the given count doesn't fit into the 32-bit dlen field
+    AtomTooLarge { count: u16, len: usize },
+}
 
 impl std::fmt::Display for EepError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        match self {
+            EepError::InvalidAtomType(atype) => write!(f, "Invalid atom type: 0x{atype:04x}"),
+            EepError::ReservedAtomType(atype) => write!(f, "Reserved atom type: 0x{atype:04x}"),
+            EepError::NotReservedAtomType(atype) => write!(
+                f,
+                "Not a reserved atom type: 0x{atype:04x} (min: 0x0006, max: 0xfffe)"
+            ),
+            EepError::VendorStringTooLong(len) => {
+                write!(f, "Vendor string to long: {len} (max: {} bytes)", u8::MAX)
+            }
+            EepError::ProductStringTooLong(len) => {
+                write!(f, "Product string to long: {len} (max: {} bytes)", u8::MAX)
+            }
+            EepError::AtomTooLarge { count, len } => write!(
+                f,
+                "The data of Atom {count} is too large: {len} bytes (max: {} bytes)",
+                u32::MAX - 2
+            ),
+        }
     }
 }
 
@@ -44,6 +88,8 @@ impl std::error::Error for EepError {}
 #[derive(Debug)]
 pub enum EepPushError {
     MaxAtomCountExceeded,
+    /// The Atom can't be serialized, e.g. its data doesn't fit into the dlen field
+    InvalidAtom(EepError),
     WrongAtomOrder {
         atype: EepAtomType,
         prev: Option<EepAtomType>,
@@ -57,6 +103,7 @@ impl std::fmt::Display for EepPushError {
             EepPushError::MaxAtomCountExceeded => {
                 write!(f, "The maximum Atom count {} was exceeded", u16::MAX)
             }
+            EepPushError::InvalidAtom(e) => write!(f, "{e}"),
             EepPushError::WrongAtomOrder {
                 atype,
                 prev,
@@ -84,6 +131,235 @@ impl std::fmt::Display for EepPushError {
 
 impl std::error::Error for EepPushError {}
 
+impl From<EepError> for EepPushError {
+    fn from(e: EepError) -> Self {
+        EepPushError::InvalidAtom(e)
+    }
+}
+
+/// Errors returned when parsing an EEPROM image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EepParseError {
+    /// The image is shorter than the EEPROM header
+    HeaderTooShort { len: usize },
+    /// The image doesn't start with the signature `R-Pi`
+    InvalidSignature(u32),
+    /// The eeplen of the header is bigger than the image
+    EepLenOutOfBounds { eeplen: u32, len: usize },
+    /// The atom header at `offset` exceeds the eeplen
+    AtomHeaderOutOfBounds { index: u16, offset: usize },
+    /// The dlen of the atom is too small to contain the CRC16
+    AtomTooShort { index: u16, dlen: u32 },
+    /// The dlen of the atom at `offset` exceeds the eeplen
+    AtomOutOfBounds {
+        index: u16,
+        offset: usize,
+        dlen: u32,
+    },
+    /// The version of the EEPROM header is not supported
+    UnsupportedVersion(u8),
+    /// The eeplen of the header doesn't match the size of the image
+    EepLenMismatch { eeplen: u32, len: usize },
+    /// The atom type is invalid or reserved
+    InvalidAtomType { index: u16, atype: u16 },
+    /// The atom is not allowed after the previous atom
+    WrongAtomOrder {
+        index: u16,
+        atype: EepAtomType,
+        prev: Option<EepAtomType>,
+    },
+    /// A required atom is missing
+    MissingAtom(EepAtomType),
+    /// The data of the atom can't be decoded
+    InvalidAtomData {
+        index: u16,
+        atype: EepAtomType,
+        reason: String,
+    },
+    /// The image ends before the number of atoms declared by numatoms
+    NumAtomsMismatch { numatoms: u16, found: u16 },
+    /// The atoms end before the eeplen of the header
+    EepLenNotConsumed { eeplen: u32, consumed: usize },
+    /// The CRC16 stored in the atom doesn't match the calculated one
+    CrcMismatch {
+        index: u16,
+        expected: u16,
+        found: u16,
+    },
+}
+
+impl std::fmt::Display for EepParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EepParseError::HeaderTooShort { len } => write!(
+                f,
+                "The image is too short for the EEPROM header: {len} bytes (min: {EEP_HEADER_LEN} bytes)"
+            ),
+            EepParseError::InvalidSignature(signature) => {
+                write!(f, "Invalid EEPROM signature: 0x{signature:08x}")
+            }
+            EepParseError::EepLenOutOfBounds { eeplen, len } => write!(
+                f,
+                "The eeplen {eeplen} exceeds the size of the image: {len} bytes"
+            ),
+            EepParseError::AtomHeaderOutOfBounds { index, offset } => write!(
+                f,
+                "The header of Atom {index} at offset 0x{offset:04x} exceeds the eeplen"
+            ),
+            EepParseError::AtomTooShort { index, dlen } => write!(
+                f,
+                "The dlen {dlen} of Atom {index} is too small for the CRC16"
+            ),
+            EepParseError::AtomOutOfBounds {
+                index,
+                offset,
+                dlen,
+            } => write!(
+                f,
+                "The data (dlen: {dlen}) of Atom {index} at offset 0x{offset:04x} exceeds the eeplen"
+            ),
+            EepParseError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported EEPROM version: {version} (expected: {EEP_VERSION})")
+            }
+            EepParseError::EepLenMismatch { eeplen, len } => write!(
+                f,
+                "The eeplen {eeplen} doesn't match the size of the image: {len} bytes"
+            ),
+            EepParseError::InvalidAtomType { index, atype } => {
+                write!(f, "Invalid type of Atom {index}: 0x{atype:04x}")
+            }
+            EepParseError::WrongAtomOrder { index, atype, prev } => match prev {
+                Some(prev) => write!(
+                    f,
+                    "Wrong Atom type order: Atom {index} is a {atype} Atom, but the previous Atom \
+                    was a {prev} Atom"
+                ),
+                None => write!(
+                    f,
+                    "Wrong Atom type order: The first Atom is a {atype} Atom"
+                ),
+            },
+            EepParseError::MissingAtom(atype) => {
+                write!(f, "The required {atype} Atom is missing")
+            }
+            EepParseError::InvalidAtomData {
+                index,
+                atype,
+                reason,
+            } => write!(f, "Invalid data of Atom {index} ({atype}): {reason}"),
+            EepParseError::NumAtomsMismatch { numatoms, found } => write!(
+                f,
+                "The header declares {numatoms} Atoms, but the image contains only {found}"
+            ),
+            EepParseError::EepLenNotConsumed { eeplen, consumed } => write!(
+                f,
+                "The Atoms end after {consumed} bytes, but the eeplen is {eeplen} bytes"
+            ),
+            EepParseError::CrcMismatch {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "CRC16 mismatch of Atom {index}: calculated 0x{expected:04x}, found 0x{found:04x}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EepParseError {}
+
+/// Return the Atom types allowed to follow an Atom of type `prev`
+///
+/// `prev` is `None` for the first Atom. The order is:
+/// ```text
+/// vendor info -> GPIO (bank 0) map -> [Linux DT blob] -> [custom data...] -> [GPIO (bank 1) map]
+/// ```
+/// Atoms of an [unknown](EepAtomType::Unknown) type are opaque and may be placed anywhere. They are
+/// skipped, so `prev` is the last Atom of a known type.
+pub fn allowed_successors(prev: Option<EepAtomType>) -> &'static [EepAtomType] {
+    match prev {
+        None => &[EepAtomType::VendorInfo],
+        Some(EepAtomType::VendorInfo) => &[EepAtomType::GpioBank0Map],
+        Some(EepAtomType::GpioBank0Map) => &[
+            EepAtomType::LinuxDTB,
+            EepAtomType::ManufCustomData,
+            EepAtomType::GpioBank1Map,
+        ],
+        Some(EepAtomType::LinuxDTB) | Some(EepAtomType::ManufCustomData) => {
+            &[EepAtomType::ManufCustomData, EepAtomType::GpioBank1Map]
+        }
+        Some(EepAtomType::GpioBank1Map) | Some(EepAtomType::Unknown) => &[],
+    }
+}
+
+/// Return the Atom types allowed to follow `atoms` in the relaxed order
+///
+/// These constraints are always enforced:
+/// * The vendor info Atom is ATOM1 and the GPIO (bank 0) map Atom is ATOM2.
+/// * The Linux DT blob and the GPIO (bank 1) map Atom occur at most once.
+///
+/// Unlike [`allowed_successors()`], the Linux DT blob, custom data and GPIO (bank 1) map Atoms may
+/// follow the GPIO (bank 0) map in any order.
+pub fn relaxed_successors(atoms: &[EepAtom]) -> Vec<EepAtomType> {
+    let prev = last_known_atype(atoms);
+    if matches!(prev, None | Some(EepAtomType::VendorInfo)) {
+        return allowed_successors(prev).to_vec();
+    }
+    let present = |atype| atoms.iter().any(|atom| atom.atype == atype);
+    [
+        EepAtomType::LinuxDTB,
+        EepAtomType::ManufCustomData,
+        EepAtomType::GpioBank1Map,
+    ]
+    .into_iter()
+    .filter(|&atype| atype == EepAtomType::ManufCustomData || !present(atype))
+    .collect()
+}
+
+/// Return the type of the last Atom of a known type (see [`allowed_successors()`])
+fn last_known_atype(atoms: &[EepAtom]) -> Option<EepAtomType> {
+    atoms
+        .iter()
+        .rev()
+        .map(|atom| atom.atype)
+        .find(|&atype| atype != EepAtomType::Unknown)
+}
+
+/// A violation of the minimum requirements of the HAT specification (see
+/// [`Eep::validate_hat_compliance()`])
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComplianceIssue {
+    /// A required Atom is missing
+    MissingAtom { atype: EepAtomType },
+    /// A required Atom is not at the expected position (ATOM1 has index 0)
+    MisplacedAtom {
+        atype: EepAtomType,
+        index: usize,
+        expected: usize,
+    },
+}
+
+impl std::fmt::Display for ComplianceIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComplianceIssue::MissingAtom { atype } => {
+                write!(f, "The required {atype} Atom is missing")
+            }
+            ComplianceIssue::MisplacedAtom {
+                atype,
+                index,
+                expected,
+            } => write!(
+                f,
+                "The {atype} Atom is ATOM{} (expected: ATOM{})",
+                index + 1,
+                expected + 1
+            ),
+        }
+    }
+}
+
 /// This struct implemnts the EEPROM Structure
 ///
 /// [EEPROM Structure](https://github.com/raspberrypi/hats/blob/9616b5cd2bdf3e1d2d0330611387d639c1916100/eeprom-format.md#eeprom-structure):
@@ -96,7 +372,7 @@ impl std::error::Error for EepPushError {}
 /// ATOMn
 /// ```
 /// The HEADER is not part of this struct as it is generated on demand.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Eep {
     /// This vector contains the ATOMs (ATOM1...ATOMn)
     atoms: Vec<EepAtom>,
@@ -114,83 +390,403 @@ impl Eep {
         Eep { atoms }
     }
 
-    pub fn push(&mut self, mut atom: EepAtom) -> Result<(), EepPushError> {
-        if self.atoms.len() > u16::MAX as usize + 1 {
+    /// Append an Atom
+    ///
+    /// The count of the Atom is set to its index. The numatoms of the EEPROM header is a 16-bit
+    /// field, so at most `u16::MAX` Atoms can be added. The data of the Atom must fit into the
+    /// 32-bit dlen field. The Atoms must follow the order defined by [`allowed_successors()`].
+    pub fn push(&mut self, atom: EepAtom) -> Result<(), EepPushError> {
+        self.push_atom(atom, false)
+    }
+
+    /// Append an Atom in the relaxed order
+    ///
+    /// Like [`Eep::push()`], but the Atoms after the GPIO (bank 0) map may follow in any order (see
+    /// [`relaxed_successors()`]), e.g. custom data Atoms before the Linux DT blob Atom. This is
+    /// meant for experimental images: [`Eep::from_bytes()`] only accepts the strict order.
+    pub fn push_relaxed(&mut self, atom: EepAtom) -> Result<(), EepPushError> {
+        self.push_atom(atom, true)
+    }
+
+    fn push_atom(&mut self, mut atom: EepAtom, relaxed: bool) -> Result<(), EepPushError> {
+        if self.atoms.len() >= u16::MAX as usize {
             return Err(EepPushError::MaxAtomCountExceeded);
         }
+        // Checked here, so serializing the image can't fail
+        atom_dlen(self.atoms.len() as u16, atom.data.len())?;
 
-        if self.atoms.is_empty() && atom.atype != EepAtomType::VendorInfo {
+        let prev = last_known_atype(&self.atoms);
+        let expected = if relaxed {
+            relaxed_successors(&self.atoms)
+        } else {
+            allowed_successors(prev).to_vec()
+        };
+        if atom.atype != EepAtomType::Unknown && !expected.contains(&atom.atype) {
             return Err(EepPushError::WrongAtomOrder {
                 atype: atom.atype,
-                prev: None,
-                expected: vec![EepAtomType::VendorInfo],
+                prev,
+                expected,
             });
-        };
+        }
 
-        let last = self
-            .atoms
-            .last()
-            .expect("BUG: The Atoms vector should not be empty at this point.");
-
-        match last.atype {
-            EepAtomType::VendorInfo => match atom.atype {
-                EepAtomType::GpioBank0Map => (),
-                _ => {
-                    return Err(EepPushError::WrongAtomOrder {
-                        atype: atom.atype,
-                        prev: Some(last.atype),
-                        expected: vec![EepAtomType::GpioBank0Map],
-                    });
-                }
-            },
-            EepAtomType::GpioBank0Map => match atom.atype {
-                EepAtomType::LinuxDTB
-                | EepAtomType::ManufCustomData
-                | EepAtomType::GpioBank1Map => (),
-                _ => {
-                    return Err(EepPushError::WrongAtomOrder {
-                        atype: atom.atype,
-                        prev: Some(last.atype),
-                        expected: vec![
-                            EepAtomType::LinuxDTB,
-                            EepAtomType::ManufCustomData,
-                            EepAtomType::GpioBank1Map,
-                        ],
-                    });
-                }
-            },
-            EepAtomType::LinuxDTB => match atom.atype {
-                EepAtomType::ManufCustomData | EepAtomType::GpioBank1Map => (),
-                _ => {
-                    return Err(EepPushError::WrongAtomOrder {
-                        atype: atom.atype,
-                        prev: Some(last.atype),
-                        expected: vec![EepAtomType::ManufCustomData, EepAtomType::GpioBank1Map],
-                    });
-                }
-            },
-            EepAtomType::ManufCustomData => match atom.atype {
-                EepAtomType::ManufCustomData | EepAtomType::GpioBank1Map => (),
-                _ => {
-                    return Err(EepPushError::WrongAtomOrder {
-                        atype: atom.atype,
-                        prev: Some(last.atype),
-                        expected: vec![EepAtomType::ManufCustomData, EepAtomType::GpioBank1Map],
-                    });
+        atom.count = self.atoms.len() as u16;
+        self.atoms.push(atom);
+        Ok(())
+    }
+
+    /// Check the minimum requirements of the HAT specification
+    ///
+    /// The HAT specification requires the vendor info Atom (ATOM1), the GPIO map Atom (ATOM2) and
+    /// the DT blob Atom (ATOM3). All missing and misplaced Atoms are reported.
+    pub fn validate_hat_compliance(&self) -> Result<(), Vec<ComplianceIssue>> {
+        const REQUIRED: [EepAtomType; 3] = [
+            EepAtomType::VendorInfo,
+            EepAtomType::GpioBank0Map,
+            EepAtomType::LinuxDTB,
+        ];
+        let mut issues = Vec::new();
+        for (expected, atype) in REQUIRED.into_iter().enumerate() {
+            if self.atoms.get(expected).map(|atom| atom.atype) == Some(atype) {
+                continue;
+            }
+            match self.atoms.iter().position(|atom| atom.atype == atype) {
+                Some(index) => issues.push(ComplianceIssue::MisplacedAtom {
+                    atype,
+                    index,
+                    expected,
+                }),
+                None => issues.push(ComplianceIssue::MissingAtom { atype }),
+            }
+        }
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// Check that the data of every Atom fits into the 32-bit dlen field
+    ///
+    /// The dlen includes the CRC16, so the data of an Atom is limited to `u32::MAX - 2` bytes.
+    /// [`Eep::push()`] rejects a larger Atom, but an Atom replaced with [`Eep::atoms_mut()`] isn't
+    /// checked. [`Eep::to_bytes()`](ToBytes::to_bytes()) panics on a larger Atom instead of
+    /// writing a truncated dlen, so call this first after such modifications.
+    pub fn validate_sizes(&self) -> Result<(), EepError> {
+        for atom in &self.atoms {
+            atom_dlen(atom.count, atom.data.len())?;
+        }
+        Ok(())
+    }
+
+    /// Return the Atoms (ATOM1...ATOMn)
+    pub fn atoms(&self) -> &[EepAtom] {
+        &self.atoms
+    }
+
+    /// Return the number of Atoms (numatoms of the EEPROM header)
+    pub fn atom_count(&self) -> usize {
+        self.atoms.len()
+    }
+
+    /// Return the Atoms (ATOM1...ATOMn) for in place modifications
+    ///
+    /// Replacing an Atom might leave a wrong atom count behind (e.g. [`EepAtom::new_custom()`]
+    /// creates an Atom with an invalid count). Call [`Eep::refresh()`] after the modifications.
+    pub fn atoms_mut(&mut self) -> &mut [EepAtom] {
+        &mut self.atoms
+    }
+
+    /// Recompute the count of each Atom by its index
+    ///
+    /// This keeps the structure consistent after the Atoms were modified with
+    /// [`Eep::atoms_mut()`].
+    pub fn refresh(&mut self) {
+        for (i, atom) in self.atoms.iter_mut().enumerate() {
+            atom.count = i as u16;
+        }
+    }
+}
+
+/// The signature of the EEPROM header (`R-Pi` in ASCII)
+const EEP_SIGNATURE: u32 = 0x6950_2d52;
+/// The version of the EEPROM format written to the header
+pub const EEP_VERSION: u8 = 1;
+/// The size of the EEPROM header (signature, version, reserved, numatoms, eeplen)
+const EEP_HEADER_LEN: usize = 4 + 1 + 1 + 2 + 4;
+/// The size of the Atom header (type, count, dlen)
+const ATOM_HEADER_LEN: usize = 2 + 2 + 4;
+
+impl Eep {
+    /// Lazily parse the Atoms of an EEPROM image
+    ///
+    /// The returned iterator yields a borrowed view of every Atom without copying its data, so a
+    /// consumer can e.g. search for a specific Atom and stop early. The header of the image is
+    /// checked first: The signature has to match and the eeplen must not exceed the buffer. All
+    /// length fields are checked against the eeplen before they are used. After the first error
+    /// the iterator doesn't yield any further items.
+    ///
+    /// The CRC16 of the Atoms is not verified by this function.
+    pub fn parse_atoms(buf: &[u8]) -> EepAtomIter<'_> {
+        EepAtomIter {
+            buf,
+            offset: 0,
+            index: 0,
+            numatoms: None,
+            done: false,
+        }
+    }
+
+    /// Decode an EEPROM image
+    ///
+    /// The header must have the signature `R-Pi`, the version 1 and an eeplen matching the size of
+    /// `buf`. The Atoms must follow the order defined by [`allowed_successors()`]. The UUID of the
+    /// vendor info Atom is expected in reversed byte order and the Linux DT blob Atom is decoded
+    /// as described in [`LinuxDTB::from_bytes()`]. [`Eep::to_bytes()`](ToBytes::to_bytes())
+    /// reproduces `buf` byte for byte. The CRC16 of every Atom is verified.
+    ///
+    /// The image must contain exactly the numatoms Atoms declared by the header and they must end
+    /// exactly at eeplen.
+    pub fn from_bytes(buf: &[u8]) -> Result<Eep, EepParseError> {
+        Eep::decode(buf, false, UuidByteOrder::Reversed)
+    }
+
+    /// Decode an EEPROM image with the UUID of the vendor info Atom in the given byte order
+    ///
+    /// This is the counterpart of [`EepAtomVendorData::set_uuid_byte_order()`] to read images
+    /// created for interoperability tests. All other checks of [`Eep::from_bytes()`] apply.
+    pub fn from_bytes_with_uuid_byte_order(
+        buf: &[u8],
+        uuid_byte_order: UuidByteOrder,
+    ) -> Result<Eep, EepParseError> {
+        Eep::decode(buf, false, uuid_byte_order)
+    }
+
+    /// Decode an EEPROM image without verifying the CRC16 of the Atoms
+    ///
+    /// This is meant for recovery tools that need to read a partially corrupted image. All other
+    /// checks of [`Eep::from_bytes()`] still apply.
+    pub fn from_bytes_lenient(buf: &[u8]) -> Result<Eep, EepParseError> {
+        Eep::decode(buf, true, UuidByteOrder::Reversed)
+    }
+
+    fn decode(
+        buf: &[u8],
+        ignore_crc: bool,
+        uuid_byte_order: UuidByteOrder,
+    ) -> Result<Eep, EepParseError> {
+        if buf.len() >= EEP_HEADER_LEN {
+            let signature = u32::from_le_bytes(buf[0..4].try_into().unwrap());
+            if signature != EEP_SIGNATURE {
+                return Err(EepParseError::InvalidSignature(signature));
+            }
+            if buf[4] != EEP_VERSION {
+                return Err(EepParseError::UnsupportedVersion(buf[4]));
+            }
+            let eeplen = u32::from_le_bytes(buf[8..12].try_into().unwrap());
+            if eeplen as usize != buf.len() {
+                return Err(EepParseError::EepLenMismatch {
+                    eeplen,
+                    len: buf.len(),
+                });
+            }
+        }
+
+        let mut atoms: Vec<EepAtom> = Vec::new();
+        let mut consumed = EEP_HEADER_LEN;
+        for atom in Eep::parse_atoms(buf) {
+            let atom = match atom {
+                Ok(atom) => atom,
+                // The image ended at eeplen before all Atoms of numatoms were found
+                Err(EepParseError::AtomHeaderOutOfBounds { index, offset })
+                    if offset == buf.len() =>
+                {
+                    return Err(EepParseError::NumAtomsMismatch {
+                        numatoms: u16::from_le_bytes([buf[6], buf[7]]),
+                        found: index,
+                    })
                 }
-            },
-            EepAtomType::GpioBank1Map => {
-                return Err(EepPushError::WrongAtomOrder {
+                Err(e) => return Err(e),
+            };
+            let index = atoms.len() as u16;
+            consumed = atom.offset + ATOM_HEADER_LEN + atom.data.len() + 2;
+            if !ignore_crc && atom.calc_crc16() != atom.crc16 {
+                return Err(EepParseError::CrcMismatch {
+                    index,
+                    expected: atom.calc_crc16(),
+                    found: atom.crc16,
+                });
+            }
+            if atom.atype == 0x0000 || atom.atype == 0xffff {
+                return Err(EepParseError::InvalidAtomType {
+                    index,
                     atype: atom.atype,
-                    prev: Some(last.atype),
-                    expected: Vec::new(),
                 });
             }
+            // Reserved types are kept as opaque Atoms of an unknown type
+            let atype = atom.atom_type().unwrap_or(EepAtomType::Unknown);
+            let prev = last_known_atype(&atoms);
+            if atype != EepAtomType::Unknown && !allowed_successors(prev).contains(&atype) {
+                return Err(EepParseError::WrongAtomOrder { index, atype, prev });
+            }
+            let data = EepAtomData::from_bytes(atom.atype, atom.data, uuid_byte_order).map_err(
+                |reason| EepParseError::InvalidAtomData {
+                    index,
+                    atype,
+                    reason,
+                },
+            )?;
+            atoms.push(EepAtom {
+                atype,
+                count: atom.count,
+                data,
+            });
         }
+        if consumed != buf.len() {
+            return Err(EepParseError::EepLenNotConsumed {
+                eeplen: buf.len() as u32,
+                consumed,
+            });
+        }
+        match atoms.len() {
+            0 => Err(EepParseError::MissingAtom(EepAtomType::VendorInfo)),
+            1 => Err(EepParseError::MissingAtom(EepAtomType::GpioBank0Map)),
+            _ => Ok(Eep { atoms }),
+        }
+    }
+}
 
-        atom.count = self.atoms.len() as u16;
-        self.atoms.push(atom);
-        Ok(())
+/// A borrowed view of an Atom in an EEPROM image (see [`Eep::parse_atoms()`])
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EepAtomRef<'a> {
+    /// The raw atom type, including invalid and reserved values
+    pub atype: u16,
+    /// The atom count
+    pub count: u16,
+    /// The atom data without the CRC16
+    pub data: &'a [u8],
+    /// The CRC16 stored in the image
+    pub crc16: u16,
+    /// The offset of the Atom in the image
+    pub offset: usize,
+    /// The Atom without the CRC16 (type, count, dlen and data)
+    raw: &'a [u8],
+}
+
+impl EepAtomRef<'_> {
+    /// Calculate the CRC16 of the Atom (type, count, dlen and data)
+    pub fn calc_crc16(&self) -> u16 {
+        ATOM_CRC16.checksum(self.raw)
+    }
+
+    /// Return the [`EepAtomType`] of the Atom or `None` for invalid and reserved types
+    pub fn atom_type(&self) -> Option<EepAtomType> {
+        EepAtomType::try_from(self.atype).ok()
+    }
+}
+
+/// The iterator returned by [`Eep::parse_atoms()`]
+#[derive(Debug)]
+pub struct EepAtomIter<'a> {
+    /// The image; limited to eeplen after the header has been parsed
+    buf: &'a [u8],
+    /// The offset of the next Atom
+    offset: usize,
+    /// The index of the next Atom
+    index: u16,
+    /// The numatoms of the header; `None` until the header has been parsed
+    numatoms: Option<u16>,
+    /// Set after the last Atom or an error
+    done: bool,
+}
+
+impl<'a> EepAtomIter<'a> {
+    fn parse_header(&mut self) -> Result<u16, EepParseError> {
+        let buf = self.buf;
+        if buf.len() < EEP_HEADER_LEN {
+            return Err(EepParseError::HeaderTooShort { len: buf.len() });
+        }
+        let signature = u32::from_le_bytes(buf[0..4].try_into().unwrap());
+        if signature != EEP_SIGNATURE {
+            return Err(EepParseError::InvalidSignature(signature));
+        }
+        let numatoms = u16::from_le_bytes(buf[6..8].try_into().unwrap());
+        let eeplen = u32::from_le_bytes(buf[8..12].try_into().unwrap());
+        if eeplen as usize > buf.len() {
+            return Err(EepParseError::EepLenOutOfBounds {
+                eeplen,
+                len: buf.len(),
+            });
+        }
+        self.buf = &buf[..eeplen as usize];
+        self.offset = EEP_HEADER_LEN;
+        Ok(numatoms)
+    }
+
+    fn parse_atom(&mut self) -> Result<EepAtomRef<'a>, EepParseError> {
+        let index = self.index;
+        let offset = self.offset;
+        let remaining = &self.buf[offset.min(self.buf.len())..];
+        if remaining.len() < ATOM_HEADER_LEN {
+            return Err(EepParseError::AtomHeaderOutOfBounds { index, offset });
+        }
+        let atype = u16::from_le_bytes(remaining[0..2].try_into().unwrap());
+        let count = u16::from_le_bytes(remaining[2..4].try_into().unwrap());
+        let dlen = u32::from_le_bytes(remaining[4..8].try_into().unwrap());
+        if dlen < 2 {
+            return Err(EepParseError::AtomTooShort { index, dlen });
+        }
+        if dlen as usize > remaining.len() - ATOM_HEADER_LEN {
+            return Err(EepParseError::AtomOutOfBounds {
+                index,
+                offset,
+                dlen,
+            });
+        }
+        let end = ATOM_HEADER_LEN + dlen as usize;
+        let data = &remaining[ATOM_HEADER_LEN..end - 2];
+        let crc16 = u16::from_le_bytes(remaining[end - 2..end].try_into().unwrap());
+        self.offset += end;
+        Ok(EepAtomRef {
+            atype,
+            count,
+            data,
+            crc16,
+            offset,
+            raw: &remaining[..end - 2],
+        })
+    }
+}
+
+impl<'a> Iterator for EepAtomIter<'a> {
+    type Item = Result<EepAtomRef<'a>, EepParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let numatoms = match self.numatoms {
+            Some(numatoms) => numatoms,
+            None => match self.parse_header() {
+                Ok(numatoms) => {
+                    self.numatoms = Some(numatoms);
+                    numatoms
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            },
+        };
+        if self.index >= numatoms {
+            self.done = true;
+            return None;
+        }
+        let atom = self.parse_atom();
+        match atom {
+            Ok(_) => self.index += 1,
+            Err(_) => self.done = true,
+        }
+        Some(atom)
     }
 }
 
@@ -204,7 +800,7 @@ impl ToBytes for Eep {
          * 2       numatoms
          * 4       eeplen
          */
-        let mut len = 4 + 1 + 1 + 2 + 4;
+        let mut len = EEP_HEADER_LEN;
         for atom in &self.atoms {
             len += atom.len();
         }
@@ -212,23 +808,825 @@ impl ToBytes for Eep {
     }
 
     fn to_bytes(&self, buf: &mut Vec<u8>) {
-        let signature = 0x6950_2d52u32;
-        buf.extend(signature.to_le_bytes());
-        // version
-        buf.push(1);
+        let start = buf.len();
+        let eeplen = self.len();
+        self.header_to_bytes(buf);
+        for atom in &self.atoms {
+            atom.to_bytes(buf);
+        }
+        // A mismatch is caused by a wrong len() implementation of an Atom
+        debug_assert_eq!(
+            buf.len() - start,
+            eeplen,
+            "BUG: The eeplen doesn't match the written bytes"
+        );
+    }
+
+    /// Write the header and then each Atom, so only a single Atom is held in memory
+    fn to_writer<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let eeplen = self.len();
+        let mut w = CountingWriter { inner: w, count: 0 };
+        let mut header = Vec::with_capacity(EEP_HEADER_LEN);
+        self.header_to_bytes(&mut header);
+        w.write_all(&header)?;
+        for atom in &self.atoms {
+            atom.to_writer(&mut w)?;
+        }
+        // A mismatch is caused by a wrong len() implementation of an Atom
+        debug_assert_eq!(
+            w.count, eeplen,
+            "BUG: The eeplen doesn't match the written bytes"
+        );
+        Ok(())
+    }
+}
+
+/// A writer counting the bytes written to the `inner` writer
+struct CountingWriter<'a, W> {
+    inner: &'a mut W,
+    count: usize,
+}
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Eep {
+    /// Calculate the CRC16 of the whole image
+    ///
+    /// The checksum is calculated over the bytes written by
+    /// [`Eep::to_bytes()`](ToBytes::to_bytes()) with the algorithm of the Atom CRC16
+    /// (CRC-16/ARC). It is not stored in the image, but serves as a fingerprint of it.
+    pub fn image_crc(&self) -> u16 {
+        let mut buf = Vec::with_capacity(self.len());
+        self.to_bytes(&mut buf);
+        ATOM_CRC16.checksum(&buf)
+    }
+
+    fn header_to_bytes(&self, buf: &mut Vec<u8>) {
+        buf.extend(EEP_SIGNATURE.to_le_bytes());
+        buf.push(EEP_VERSION);
         // reserved
         buf.push(0);
         // numatoms
         buf.extend((self.atoms.len() as u16).to_le_bytes());
         // eeplen
         buf.extend((self.len() as u32).to_le_bytes());
-        for atom in &self.atoms {
-            atom.to_bytes(buf);
+    }
+}
+
+#[test]
+fn test_to_writer() {
+    let eep = test_eep();
+    let mut buf = Vec::new();
+    eep.to_bytes(&mut buf);
+    let mut written = Vec::new();
+    eep.to_writer(&mut written).unwrap();
+    assert_eq!(written, buf);
+    let mut written = Vec::new();
+    eep.atoms()[0].to_writer(&mut written).unwrap();
+    assert_eq!(
+        written,
+        buf[EEP_HEADER_LEN..EEP_HEADER_LEN + eep.atoms()[0].len()]
+    );
+}
+
+#[test]
+fn test_image_crc() {
+    // The check value of CRC-16/ARC
+    assert_eq!(ATOM_CRC16.checksum(b"123456789"), 0xbb3d);
+
+    let mut eep = test_eep();
+    let mut buf = Vec::new();
+    eep.to_bytes(&mut buf);
+    assert_eq!(eep.image_crc(), ATOM_CRC16.checksum(&buf));
+    assert_eq!(eep.image_crc(), test_eep().image_crc());
+    let crc = eep.image_crc();
+    eep.push(EepAtom::new_custom(EepAtomCustomData::new(b"43".to_vec())))
+        .unwrap();
+    assert_ne!(eep.image_crc(), crc);
+}
+
+#[cfg(test)]
+fn test_eep() -> Eep {
+    let vendor = EepAtomVendorData::new(
+        uuid::uuid!("67e55044-10b1-426f-9247-bb680e5fe0c8"),
+        123u16,
+        3u16,
+        "ACME Technology Company".to_string(),
+        "Special Sensor Board".to_string(),
+    )
+    .unwrap();
+    let gpio_map = EepAtomGpioMapData::new(
+        gpio_map::GpioBank::Bank0,
+        gpio_map::GpioDrive::Default,
+        gpio_map::GpioSlew::Default,
+        gpio_map::GpioHysteresis::Default,
+        gpio_map::GpioBackPower::None,
+    );
+    let mut eep = Eep::new(vendor, gpio_map);
+    eep.push(EepAtom::new_linux_dtb(EepAtomLinuxDTBData::new(
+        LinuxDTB::Name("acme-sensor".to_string()),
+    )))
+    .unwrap();
+    eep.push(EepAtom::new_custom(EepAtomCustomData::new(b"42".to_vec())))
+        .unwrap();
+    eep
+}
+
+#[test]
+fn test_large_dtb() {
+    // A compiled device tree of some kilobytes
+    let mut blob = vec![0xd0, 0x0d, 0xfe, 0xed];
+    blob.extend((0..64 * 1024).map(|i| (i % 251) as u8));
+    let mut eep = test_eep();
+    // Keep the vendor info and the gpio map
+    eep.atoms.truncate(2);
+    eep.push(EepAtom::new_linux_dtb(EepAtomLinuxDTBData::new(
+        LinuxDTB::Blob(blob.clone()),
+    )))
+    .unwrap();
+
+    let mut buf = Vec::with_capacity(eep.len());
+    let capacity = buf.capacity();
+    eep.to_bytes(&mut buf);
+    // The buffer is filled without reallocation
+    assert_eq!(buf.len(), eep.len());
+    assert_eq!(buf.capacity(), capacity);
+
+    let decoded = Eep::from_bytes(&buf).unwrap();
+    assert!(decoded.atoms().iter().any(|atom| matches!(
+        atom.data(),
+        EepAtomData::LinuxDTB(dtb) if dtb.data() == &LinuxDTB::Blob(blob.clone())
+    )));
+}
+
+#[test]
+fn test_parse_atoms() {
+    let mut buf = Vec::new();
+    test_eep().to_bytes(&mut buf);
+
+    let atoms: Vec<EepAtomRef> = Eep::parse_atoms(&buf).map(|a| a.unwrap()).collect();
+    assert_eq!(atoms.len(), 4);
+    for (i, atom) in atoms.iter().enumerate() {
+        assert_eq!(atom.count as usize, i);
+    }
+    assert_eq!(atoms[0].atom_type(), Some(EepAtomType::VendorInfo));
+    assert_eq!(atoms[0].offset, EEP_HEADER_LEN);
+    assert_eq!(atoms[1].atom_type(), Some(EepAtomType::GpioBank0Map));
+    assert_eq!(atoms[1].data.len(), 30);
+    assert_eq!(atoms[2].atom_type(), Some(EepAtomType::LinuxDTB));
+    assert_eq!(atoms[2].data, b"acme-sensor");
+    assert_eq!(atoms[3].atom_type(), Some(EepAtomType::ManufCustomData));
+    assert_eq!(atoms[3].data, b"42");
+    let end = atoms[3].offset + ATOM_HEADER_LEN + 2 + 2;
+    assert_eq!(
+        atoms[3].crc16,
+        u16::from_le_bytes([buf[end - 2], buf[end - 1]])
+    );
+
+    // Stop early after the first match
+    let dtb = Eep::parse_atoms(&buf)
+        .find(|a| matches!(a, Ok(a) if a.atype == EepAtomType::LinuxDTB as u16))
+        .unwrap()
+        .unwrap();
+    assert_eq!(dtb.count, 2);
+}
+
+#[test]
+fn test_parse_atoms_errors() {
+    let mut buf = Vec::new();
+    test_eep().to_bytes(&mut buf);
+
+    // Every truncated image has to fail gracefully and stop after the first error
+    for len in 0..buf.len() {
+        let results: Vec<_> = Eep::parse_atoms(&buf[..len]).collect();
+        assert!(results.last().unwrap().is_err(), "truncated to {len} bytes");
+        assert_eq!(results.iter().filter(|r| r.is_err()).count(), 1);
+    }
+    assert_eq!(
+        Eep::parse_atoms(&buf[..4]).next(),
+        Some(Err(EepParseError::HeaderTooShort { len: 4 }))
+    );
+
+    let mut bad = buf.clone();
+    bad[0] = b'X';
+    assert!(matches!(
+        Eep::parse_atoms(&bad).next(),
+        Some(Err(EepParseError::InvalidSignature(_)))
+    ));
+
+    // Oversized eeplen
+    let mut bad = buf.clone();
+    bad[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(
+        Eep::parse_atoms(&bad).next(),
+        Some(Err(EepParseError::EepLenOutOfBounds {
+            eeplen: u32::MAX,
+            len: buf.len()
+        }))
+    );
+
+    // Oversized dlen of the first atom
+    let mut bad = buf.clone();
+    bad[EEP_HEADER_LEN + 4..EEP_HEADER_LEN + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(
+        Eep::parse_atoms(&bad).next(),
+        Some(Err(EepParseError::AtomOutOfBounds {
+            index: 0,
+            offset: EEP_HEADER_LEN,
+            dlen: u32::MAX
+        }))
+    );
+
+    // dlen too small for the CRC16
+    let mut bad = buf.clone();
+    bad[EEP_HEADER_LEN + 4..EEP_HEADER_LEN + 8].copy_from_slice(&1u32.to_le_bytes());
+    assert_eq!(
+        Eep::parse_atoms(&bad).next(),
+        Some(Err(EepParseError::AtomTooShort { index: 0, dlen: 1 }))
+    );
+
+    // numatoms bigger than the atoms in the image
+    let mut bad = buf.clone();
+    bad[6..8].copy_from_slice(&5u16.to_le_bytes());
+    let results: Vec<_> = Eep::parse_atoms(&bad).collect();
+    assert_eq!(results.len(), 5);
+    assert!(matches!(
+        results[4],
+        Err(EepParseError::AtomHeaderOutOfBounds { index: 4, .. })
+    ));
+}
+
+#[test]
+fn test_eeplen() {
+    let eep = test_eep();
+    let mut buf = vec![0xff; 3];
+    eep.to_bytes(&mut buf);
+    let buf = &buf[3..];
+
+    let atoms_len: usize = eep.atoms().iter().map(|atom| atom.len()).sum();
+    assert_eq!(eep.len(), EEP_HEADER_LEN + atoms_len);
+    assert_eq!(buf.len(), eep.len());
+    let eeplen = u32::from_le_bytes(buf[8..12].try_into().unwrap());
+    assert_eq!(eeplen as usize, buf.len());
+
+    for atom in eep.atoms() {
+        let mut atom_buf = Vec::new();
+        atom.to_bytes(&mut atom_buf);
+        assert_eq!(
+            atom_buf.len(),
+            atom.len(),
+            "wrong len() of the {}",
+            atom.atype()
+        );
+    }
+}
+
+#[test]
+fn test_validate_hat_compliance() {
+    let mut eep = test_eep();
+    assert_eq!(eep.validate_hat_compliance(), Ok(()));
+
+    // Without the DT blob
+    eep.atoms.remove(2);
+    assert_eq!(
+        eep.validate_hat_compliance(),
+        Err(vec![ComplianceIssue::MissingAtom {
+            atype: EepAtomType::LinuxDTB
+        }])
+    );
+
+    let mut eep = test_eep();
+    eep.atoms_mut().swap(2, 3);
+    assert_eq!(
+        eep.validate_hat_compliance(),
+        Err(vec![ComplianceIssue::MisplacedAtom {
+            atype: EepAtomType::LinuxDTB,
+            index: 3,
+            expected: 2
+        }])
+    );
+}
+
+#[test]
+fn test_push_order() {
+    assert_eq!(allowed_successors(None), &[EepAtomType::VendorInfo]);
+    assert!(allowed_successors(Some(EepAtomType::GpioBank1Map)).is_empty());
+
+    let mut eep = test_eep();
+    let err = eep
+        .push(EepAtom::new_linux_dtb(EepAtomLinuxDTBData::new(
+            LinuxDTB::Name("second".to_string()),
+        )))
+        .unwrap_err();
+    match err {
+        EepPushError::WrongAtomOrder {
+            atype,
+            prev,
+            expected,
+        } => {
+            assert_eq!(atype, EepAtomType::LinuxDTB);
+            assert_eq!(prev, Some(EepAtomType::ManufCustomData));
+            assert_eq!(
+                expected,
+                vec![EepAtomType::ManufCustomData, EepAtomType::GpioBank1Map]
+            );
         }
+        _ => panic!("unexpected error: {err}"),
     }
+
+    let gpio_map = EepAtomGpioMapData::new(
+        gpio_map::GpioBank::Bank1,
+        gpio_map::GpioDrive::Default,
+        gpio_map::GpioSlew::Default,
+        gpio_map::GpioHysteresis::Default,
+        gpio_map::GpioBackPower::None,
+    );
+    eep.push(EepAtom::new_gpio_bank1_map(gpio_map)).unwrap();
+    assert_eq!(eep.atoms().last().unwrap().count(), 4);
+    assert!(eep
+        .push(EepAtom::new_custom(EepAtomCustomData::new(Vec::new())))
+        .is_err());
 }
 
-#[derive(Debug)]
+#[test]
+fn test_push_relaxed() {
+    let mut eep = test_eep();
+    // Keep the vendor info and the gpio map
+    eep.atoms.truncate(2);
+    let custom = EepAtom::new_custom(EepAtomCustomData::new(b"42".to_vec()));
+    let dtb = EepAtom::new_linux_dtb(EepAtomLinuxDTBData::new(LinuxDTB::Name(
+        "acme-sensor".to_string(),
+    )));
+    let gpio_map = EepAtom::new_gpio_bank1_map(EepAtomGpioMapData::new(
+        gpio_map::GpioBank::Bank1,
+        gpio_map::GpioDrive::Default,
+        gpio_map::GpioSlew::Default,
+        gpio_map::GpioHysteresis::Default,
+        gpio_map::GpioBackPower::None,
+    ));
+
+    // The strict order doesn't allow the DT blob after a custom atom
+    let mut strict = eep.clone();
+    strict.push(custom.clone()).unwrap();
+    assert!(strict.push(dtb.clone()).is_err());
+
+    eep.push_relaxed(custom.clone()).unwrap();
+    eep.push_relaxed(gpio_map.clone()).unwrap();
+    eep.push_relaxed(dtb.clone()).unwrap();
+    eep.push_relaxed(custom.clone()).unwrap();
+    let counts: Vec<u16> = eep.atoms().iter().map(|atom| atom.count()).collect();
+    assert_eq!(counts, [0, 1, 2, 3, 4, 5]);
+
+    // Only custom data Atoms may be added once the DT blob and the bank 1 map are present
+    assert_eq!(
+        relaxed_successors(eep.atoms()),
+        [EepAtomType::ManufCustomData]
+    );
+    for atom in [dtb, gpio_map, test_eep().atoms()[0].clone()] {
+        assert!(matches!(
+            eep.push_relaxed(atom),
+            Err(EepPushError::WrongAtomOrder { .. })
+        ));
+    }
+
+    // The first two Atoms are fixed
+    assert_eq!(
+        relaxed_successors(&eep.atoms()[..1]),
+        [EepAtomType::GpioBank0Map]
+    );
+    assert_eq!(relaxed_successors(&[]), [EepAtomType::VendorInfo]);
+
+    // The relaxed order is not accepted by the decoder
+    let mut buf = Vec::new();
+    eep.to_bytes(&mut buf);
+    assert!(matches!(
+        Eep::from_bytes(&buf),
+        Err(EepParseError::WrongAtomOrder { index: 4, .. })
+    ));
+}
+
+#[test]
+fn test_push_max_atom_count() {
+    let mut eep = test_eep();
+    while eep.atom_count() < u16::MAX as usize {
+        eep.push(EepAtom::new_custom(EepAtomCustomData::new(Vec::new())))
+            .unwrap();
+    }
+    assert_eq!(eep.atoms().last().unwrap().count(), u16::MAX - 1);
+    assert!(matches!(
+        eep.push(EepAtom::new_custom(EepAtomCustomData::new(Vec::new()))),
+        Err(EepPushError::MaxAtomCountExceeded)
+    ));
+    assert_eq!(eep.atom_count(), u16::MAX as usize);
+
+    // The numatoms of the header matches the number of Atoms
+    let mut buf = Vec::new();
+    eep.to_bytes(&mut buf);
+    assert_eq!(u16::from_le_bytes([buf[6], buf[7]]), u16::MAX);
+}
+
+#[test]
+fn test_from_bytes() {
+    let mut eep = test_eep();
+    let mut gpio_map = EepAtomGpioMapData::new(
+        gpio_map::GpioBank::Bank1,
+        gpio_map::GpioDrive::Drive16mA,
+        gpio_map::GpioSlew::RateLimiting,
+        gpio_map::GpioHysteresis::Disable,
+        gpio_map::GpioBackPower::BackPower1A3,
+    );
+    gpio_map
+        .set(
+            30,
+            gpio_map::GpioPin::new(gpio_map::GpioFsel::Alt2, gpio_map::GpioPull::Up, true),
+        )
+        .unwrap();
+    eep.push(EepAtom::new_gpio_bank1_map(gpio_map)).unwrap();
+    let mut buf = Vec::new();
+    eep.to_bytes(&mut buf);
+
+    let decoded = Eep::from_bytes(&buf).unwrap();
+    assert_eq!(decoded.atoms().len(), 5);
+    assert_eq!(decoded, eep);
+    // The DT overlay name is decoded as name again
+    assert!(matches!(
+        decoded.atoms()[2].data(),
+        EepAtomData::LinuxDTB(dtb) if dtb.data() == &LinuxDTB::Name("acme-sensor".to_string())
+    ));
+    assert_eq!(decoded.clone(), decoded);
+    let mut decoded_buf = Vec::new();
+    decoded.to_bytes(&mut decoded_buf);
+    assert_eq!(buf, decoded_buf);
+
+    let mut bad = buf.clone();
+    bad[3] = 0;
+    assert!(matches!(
+        Eep::from_bytes(&bad),
+        Err(EepParseError::InvalidSignature(_))
+    ));
+
+    let mut bad = buf.clone();
+    bad[4] = 2;
+    assert_eq!(
+        Eep::from_bytes(&bad).unwrap_err(),
+        EepParseError::UnsupportedVersion(2)
+    );
+
+    let mut bad = buf.clone();
+    bad.push(0xff);
+    assert_eq!(
+        Eep::from_bytes(&bad).unwrap_err(),
+        EepParseError::EepLenMismatch {
+            eeplen: buf.len() as u32,
+            len: buf.len() + 1
+        }
+    );
+
+    let mut bad = buf.clone();
+    bad[EEP_HEADER_LEN..EEP_HEADER_LEN + 2].copy_from_slice(&0xffffu16.to_le_bytes());
+    assert_eq!(
+        Eep::from_bytes_lenient(&bad).unwrap_err(),
+        EepParseError::InvalidAtomType {
+            index: 0,
+            atype: 0xffff
+        }
+    );
+
+    // A GPIO map where the vendor info is expected
+    let mut bad = buf.clone();
+    bad[EEP_HEADER_LEN..EEP_HEADER_LEN + 2].copy_from_slice(&0x0002u16.to_le_bytes());
+    assert_eq!(
+        Eep::from_bytes_lenient(&bad).unwrap_err(),
+        EepParseError::WrongAtomOrder {
+            index: 0,
+            atype: EepAtomType::GpioBank0Map,
+            prev: None
+        }
+    );
+
+    // vslen doesn't match the size of the vendor info
+    let mut bad = buf.clone();
+    bad[EEP_HEADER_LEN + ATOM_HEADER_LEN + 20] += 1;
+    assert!(matches!(
+        Eep::from_bytes_lenient(&bad),
+        Err(EepParseError::InvalidAtomData {
+            index: 0,
+            atype: EepAtomType::VendorInfo,
+            ..
+        })
+    ));
+
+    // Only the vendor info
+    let vendor_len = eep.atoms()[0].len();
+    let mut bad = buf[..EEP_HEADER_LEN + vendor_len].to_vec();
+    bad[6..8].copy_from_slice(&1u16.to_le_bytes());
+    bad[8..12].copy_from_slice(&((EEP_HEADER_LEN + vendor_len) as u32).to_le_bytes());
+    assert_eq!(
+        Eep::from_bytes(&bad).unwrap_err(),
+        EepParseError::MissingAtom(EepAtomType::GpioBank0Map)
+    );
+
+    // Truncated images must fail gracefully
+    for len in 0..buf.len() {
+        assert!(Eep::from_bytes(&buf[..len]).is_err());
+    }
+}
+
+#[test]
+fn test_from_bytes_uuid_byte_order() {
+    let uuid = uuid::uuid!("67e55044-10b1-426f-9247-bb680e5fe0c8");
+    let mut vendor = EepAtomVendorData::new(
+        uuid,
+        123u16,
+        3u16,
+        "ACME Technology Company".to_string(),
+        "Special Sensor Board".to_string(),
+    )
+    .unwrap();
+    vendor.set_uuid_byte_order(UuidByteOrder::Normal);
+    let gpio_map = EepAtomGpioMapData::new(
+        gpio_map::GpioBank::Bank0,
+        gpio_map::GpioDrive::Default,
+        gpio_map::GpioSlew::Default,
+        gpio_map::GpioHysteresis::Default,
+        gpio_map::GpioBackPower::None,
+    );
+    let eep = Eep::new(vendor, gpio_map);
+    let mut buf = Vec::new();
+    eep.to_bytes(&mut buf);
+
+    let decoded = Eep::from_bytes_with_uuid_byte_order(&buf, UuidByteOrder::Normal).unwrap();
+    assert_eq!(decoded, eep);
+    assert!(matches!(
+        decoded.atoms()[0].data(),
+        EepAtomData::VendorInfo(vendor) if vendor.uuid() == uuid
+    ));
+    let mut decoded_buf = Vec::new();
+    decoded.to_bytes(&mut decoded_buf);
+    assert_eq!(buf, decoded_buf);
+
+    // The default byte order reads the UUID reversed
+    let decoded = Eep::from_bytes(&buf).unwrap();
+    assert!(matches!(
+        decoded.atoms()[0].data(),
+        EepAtomData::VendorInfo(vendor) if vendor.uuid() != uuid
+    ));
+}
+
+#[test]
+fn test_from_bytes_header() {
+    let mut buf = Vec::new();
+    test_eep().to_bytes(&mut buf);
+    let numatoms = u16::from_le_bytes([buf[6], buf[7]]);
+
+    // numatoms too big
+    let mut bad = buf.clone();
+    bad[6..8].copy_from_slice(&(numatoms + 1).to_le_bytes());
+    assert_eq!(
+        Eep::from_bytes(&bad).unwrap_err(),
+        EepParseError::NumAtomsMismatch {
+            numatoms: numatoms + 1,
+            found: numatoms
+        }
+    );
+
+    // numatoms too small, so the last atom is not consumed
+    let mut bad = buf.clone();
+    bad[6..8].copy_from_slice(&(numatoms - 1).to_le_bytes());
+    let last_offset = Eep::parse_atoms(&buf).last().unwrap().unwrap().offset;
+    assert_eq!(
+        Eep::from_bytes(&bad).unwrap_err(),
+        EepParseError::EepLenNotConsumed {
+            eeplen: buf.len() as u32,
+            consumed: last_offset
+        }
+    );
+
+    // eeplen too big, the image has trailing bytes after the atoms
+    let mut bad = buf.clone();
+    bad.push(0xff);
+    bad[8..12].copy_from_slice(&(buf.len() as u32 + 1).to_le_bytes());
+    assert_eq!(
+        Eep::from_bytes(&bad).unwrap_err(),
+        EepParseError::EepLenNotConsumed {
+            eeplen: buf.len() as u32 + 1,
+            consumed: buf.len()
+        }
+    );
+
+    // eeplen too small, the last atom exceeds it
+    let mut bad = buf[..buf.len() - 1].to_vec();
+    bad[8..12].copy_from_slice(&(buf.len() as u32 - 1).to_le_bytes());
+    assert_eq!(
+        Eep::from_bytes(&bad).unwrap_err(),
+        EepParseError::AtomOutOfBounds {
+            index: numatoms - 1,
+            offset: last_offset,
+            dlen: 4
+        }
+    );
+}
+
+#[test]
+fn test_from_bytes_crc() {
+    let mut buf = Vec::new();
+    test_eep().to_bytes(&mut buf);
+    for atom in Eep::parse_atoms(&buf) {
+        let atom = atom.unwrap();
+        assert_eq!(atom.calc_crc16(), atom.crc16);
+    }
+
+    // Corrupt the data of the custom atom
+    let custom = Eep::parse_atoms(&buf).nth(3).unwrap().unwrap();
+    let (offset, found) = (custom.offset + ATOM_HEADER_LEN, custom.crc16);
+    buf[offset] ^= 0xff;
+    let expected = Eep::parse_atoms(&buf).nth(3).unwrap().unwrap().calc_crc16();
+    assert_eq!(
+        Eep::from_bytes(&buf).unwrap_err(),
+        EepParseError::CrcMismatch {
+            index: 3,
+            expected,
+            found
+        }
+    );
+    let eep = Eep::from_bytes_lenient(&buf).unwrap();
+    assert_ne!(eep, test_eep());
+    let mut decoded_buf = Vec::new();
+    eep.to_bytes(&mut decoded_buf);
+    assert_eq!(decoded_buf[offset], buf[offset]);
+}
+
+/// Decode `buf` with every parser, which must return errors instead of panicking
+#[cfg(test)]
+fn decode_all(buf: &[u8]) {
+    for atom in Eep::parse_atoms(buf).flatten() {
+        atom.calc_crc16();
+    }
+    for eep in [Eep::from_bytes(buf), Eep::from_bytes_lenient(buf)]
+        .into_iter()
+        .flatten()
+    {
+        // A decoded image must be serializable again
+        let mut decoded_buf = Vec::new();
+        eep.to_bytes(&mut decoded_buf);
+    }
+}
+
+#[test]
+fn test_from_bytes_robustness() {
+    let mut buf = Vec::new();
+    test_eep().to_bytes(&mut buf);
+    decode_all(&buf);
+
+    // Truncated and extended images
+    for len in 0..buf.len() {
+        decode_all(&buf[..len]);
+    }
+    let mut extended = buf.clone();
+    extended.extend([0xff; 16]);
+    decode_all(&extended);
+
+    // Corrupted headers
+    for offset in 0..EEP_HEADER_LEN {
+        for value in [0x00, 0x01, 0x7f, 0x80, 0xff] {
+            let mut corrupted = buf.clone();
+            corrupted[offset] = value;
+            decode_all(&corrupted);
+        }
+    }
+
+    // Bogus numatoms
+    for numatoms in [0, 1, 3, 5, 0x7fff, u16::MAX] {
+        let mut corrupted = buf.clone();
+        corrupted[6..8].copy_from_slice(&numatoms.to_le_bytes());
+        decode_all(&corrupted);
+    }
+
+    // Bogus dlen and atom types of every atom
+    let offsets: Vec<usize> = Eep::parse_atoms(&buf).map(|a| a.unwrap().offset).collect();
+    for offset in offsets {
+        for dlen in [0, 1, 2, 3, buf.len() as u32, u32::MAX - 1, u32::MAX] {
+            let mut corrupted = buf.clone();
+            corrupted[offset + 4..offset + 8].copy_from_slice(&dlen.to_le_bytes());
+            decode_all(&corrupted);
+        }
+        for atype in [0x0000, 0x0001, 0x0002, 0x0003, 0x0004, 0x0006, 0xffff] {
+            let mut corrupted = buf.clone();
+            corrupted[offset..offset + 2].copy_from_slice(&u16::to_le_bytes(atype));
+            decode_all(&corrupted);
+        }
+    }
+
+    // Random bytes, with and without a valid header (a simple LCG keeps the test deterministic)
+    let mut state: u32 = 0x2545_f491;
+    let mut random = || {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        (state >> 16) as u8
+    };
+    for len in [1, 8, 11, 12, 13, 20, 64, 255, 256, 1024] {
+        for _ in 0..64 {
+            let mut data: Vec<u8> = (0..len).map(|_| random()).collect();
+            decode_all(&data);
+            if len >= EEP_HEADER_LEN {
+                data[..EEP_HEADER_LEN].copy_from_slice(&buf[..EEP_HEADER_LEN]);
+                data[8..12].copy_from_slice(&(len as u32).to_le_bytes());
+                decode_all(&data);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_unknown_atoms() {
+    assert!(EepAtom::new_unknown(0x0000, vec![]).is_err());
+    assert!(EepAtom::new_unknown(0x0004, vec![]).is_err());
+    assert!(EepAtom::new_unknown(0xffff, vec![]).is_err());
+
+    let mut eep = test_eep();
+    eep.push(EepAtom::new_unknown(0x1234, vec![1, 2, 3]).unwrap())
+        .unwrap();
+    // Unknown atoms don't affect the order of the known ones
+    eep.push(EepAtom::new_custom(EepAtomCustomData::new(b"43".to_vec())))
+        .unwrap();
+    eep.push(EepAtom::new_unknown(0x0006, vec![]).unwrap())
+        .unwrap();
+    let mut buf = Vec::new();
+    eep.to_bytes(&mut buf);
+
+    let decoded = Eep::from_bytes(&buf).unwrap();
+    let atoms = decoded.atoms();
+    assert_eq!(atoms.len(), 7);
+    assert_eq!(atoms[4].atype(), EepAtomType::Unknown);
+    assert_eq!(atoms[4].raw_atype(), 0x1234);
+    assert!(matches!(
+        atoms[4].data(),
+        EepAtomData::Unknown { atype: 0x1234, raw } if raw == &[1, 2, 3]
+    ));
+    assert_eq!(atoms[5].atype(), EepAtomType::ManufCustomData);
+    assert_eq!(atoms[6].raw_atype(), 0x0006);
+    let mut decoded_buf = Vec::new();
+    decoded.to_bytes(&mut decoded_buf);
+    assert_eq!(buf, decoded_buf);
+}
+
+#[test]
+fn test_atoms() {
+    let eep = test_eep();
+    assert_eq!(eep.atom_count(), 4);
+    let atypes: Vec<EepAtomType> = eep.atoms().iter().map(|atom| atom.atype()).collect();
+    assert_eq!(
+        atypes,
+        [
+            EepAtomType::VendorInfo,
+            EepAtomType::GpioBank0Map,
+            EepAtomType::LinuxDTB,
+            EepAtomType::ManufCustomData
+        ]
+    );
+    for (i, atom) in eep.atoms().iter().enumerate() {
+        assert_eq!(atom.count() as usize, i);
+    }
+
+    let mut buf = Vec::new();
+    eep.to_bytes(&mut buf);
+    assert_eq!(
+        u16::from_le_bytes([buf[6], buf[7]]) as usize,
+        eep.atom_count()
+    );
+}
+
+#[test]
+fn test_refresh() {
+    let mut eep = test_eep();
+    let atoms = eep.atoms_mut();
+    atoms[3] = EepAtom::new_custom(EepAtomCustomData::new(b"4711".to_vec()));
+    assert_eq!(eep.atoms()[3].count(), 0xffff);
+
+    eep.refresh();
+    for (i, atom) in eep.atoms().iter().enumerate() {
+        assert_eq!(atom.count() as usize, i);
+    }
+
+    let mut buf = Vec::new();
+    eep.to_bytes(&mut buf);
+    assert_eq!(buf.len(), eep.len());
+    let atoms: Vec<EepAtomRef> = Eep::parse_atoms(&buf).map(|a| a.unwrap()).collect();
+    assert_eq!(atoms.len(), 4);
+    assert_eq!(atoms[3].count, 3);
+    assert_eq!(atoms[3].data, b"4711");
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum EepAtomData {
     /// vendor info (0x0001, [`EepAtomType::VendorInfo`])
     VendorInfo(EepAtomVendorData),
@@ -240,6 +1638,46 @@ pub enum EepAtomData {
     ManufCustomData(EepAtomCustomData),
     /// GPIO (bank 1) map (0x0005, [`EepAtomType::GpioBank1Map`])
     GpioBank1Map(gpio_map::EepAtomGpioMapData),
+    /// An Atom of a reserved type (0x0006-0xfffe, [`EepAtomType::Unknown`]), kept verbatim
+    Unknown { atype: u16, raw: Vec<u8> },
+}
+
+impl EepAtomData {
+    /// Decode the data of an Atom of type `atype`
+    ///
+    /// The data of an Atom of an unknown type is kept verbatim.
+    fn from_bytes(
+        atype: u16,
+        data: &[u8],
+        uuid_byte_order: UuidByteOrder,
+    ) -> Result<EepAtomData, String> {
+        let Ok(known) = EepAtomType::try_from(atype) else {
+            return Ok(EepAtomData::Unknown {
+                atype,
+                raw: data.to_vec(),
+            });
+        };
+        Ok(match known {
+            EepAtomType::VendorInfo => {
+                EepAtomData::VendorInfo(EepAtomVendorData::from_bytes(data, uuid_byte_order)?)
+            }
+            EepAtomType::GpioBank0Map => EepAtomData::GpioBank0Map(EepAtomGpioMapData::from_bytes(
+                gpio_map::GpioBank::Bank0,
+                data,
+            )?),
+            EepAtomType::LinuxDTB => {
+                EepAtomData::LinuxDTB(EepAtomLinuxDTBData::new(LinuxDTB::from_bytes(data)))
+            }
+            EepAtomType::ManufCustomData => {
+                EepAtomData::ManufCustomData(EepAtomCustomData::new(data.to_vec()))
+            }
+            EepAtomType::GpioBank1Map => EepAtomData::GpioBank1Map(EepAtomGpioMapData::from_bytes(
+                gpio_map::GpioBank::Bank1,
+                data,
+            )?),
+            EepAtomType::Unknown => unreachable!("BUG: TryFrom<u16> returned EepAtomType::Unknown"),
+        })
+    }
 }
 
 impl ToBytes for EepAtomData {
@@ -250,6 +1688,7 @@ impl ToBytes for EepAtomData {
             EepAtomData::LinuxDTB(data) => data.len(),
             EepAtomData::ManufCustomData(data) => data.len(),
             EepAtomData::GpioBank1Map(data) => data.len(),
+            EepAtomData::Unknown { raw, .. } => raw.len(),
         }
     }
     fn to_bytes(&self, buf: &mut Vec<u8>) {
@@ -259,6 +1698,7 @@ impl ToBytes for EepAtomData {
             EepAtomData::LinuxDTB(data) => data.to_bytes(buf),
             EepAtomData::ManufCustomData(data) => data.to_bytes(buf),
             EepAtomData::GpioBank1Map(data) => data.to_bytes(buf),
+            EepAtomData::Unknown { raw, .. } => buf.extend(raw),
         };
     }
 }
@@ -276,16 +1716,19 @@ impl ToBytes for EepAtomData {
 /// 0x0006-0xfffe = reserved for future use
 /// 0xffff = invalid
 /// ```
-/// The enume does not define any value for invalid or reserved types. Any value not defined by this
-/// enum is treated as an invalid/error.
+/// The enume does not define any value for invalid types. All reserved types are represented by
+/// [`EepAtomType::Unknown`]; the actual type is stored in [`EepAtomData::Unknown`] (see
+/// [`EepAtom::raw_atype()`]).
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-#[repr(u8)]
+#[repr(u16)]
 pub enum EepAtomType {
     VendorInfo = 0x0001,
     GpioBank0Map = 0x0002,
     LinuxDTB = 0x0003,
     ManufCustomData = 0x0004,
     GpioBank1Map = 0x0005,
+    /// A reserved type that is not known to this crate
+    Unknown = 0xffff,
 }
 
 impl std::fmt::Display for EepAtomType {
@@ -299,11 +1742,48 @@ impl std::fmt::Display for EepAtomType {
                 EepAtomType::LinuxDTB => "Linux device tree blob",
                 EepAtomType::ManufCustomData => "manufacturer custom data",
                 EepAtomType::GpioBank1Map => "GPIO (bank 1) map",
+                EepAtomType::Unknown => "unknown",
             }
         )
     }
 }
 
+impl TryFrom<u16> for EepAtomType {
+    type Error = EepError;
+
+    /// Convert the type field of an Atom into an [`EepAtomType`]
+    ///
+    /// The invalid types 0x0000 and 0xffff as well as the reserved types are rejected, so this never
+    /// returns [`EepAtomType::Unknown`].
+    fn try_from(atype: u16) -> Result<Self, Self::Error> {
+        match atype {
+            0x0001 => Ok(EepAtomType::VendorInfo),
+            0x0002 => Ok(EepAtomType::GpioBank0Map),
+            0x0003 => Ok(EepAtomType::LinuxDTB),
+            0x0004 => Ok(EepAtomType::ManufCustomData),
+            0x0005 => Ok(EepAtomType::GpioBank1Map),
+            0x0000 | 0xffff => Err(EepError::InvalidAtomType(atype)),
+            _ => Err(EepError::ReservedAtomType(atype)),
+        }
+    }
+}
+
+#[test]
+fn test_eep_atom_type_try_from() {
+    for atype in [
+        EepAtomType::VendorInfo,
+        EepAtomType::GpioBank0Map,
+        EepAtomType::LinuxDTB,
+        EepAtomType::ManufCustomData,
+        EepAtomType::GpioBank1Map,
+    ] {
+        assert_eq!(EepAtomType::try_from(atype as u16).unwrap(), atype);
+    }
+    for atype in [0x0000, 0x0006, 0x1234, 0xfffe, 0xffff] {
+        assert!(EepAtomType::try_from(atype).is_err());
+    }
+}
+
 /// This struct implements the Atom Structure
 ///
 /// [Atom Structure](https://github.com/raspberrypi/hats/blob/9616b5cd2bdf3e1d2d0330611387d639c1916100/eeprom-format.md#atom-structure):
@@ -316,7 +1796,7 @@ impl std::fmt::Display for EepAtomType {
 /// 2       crc16       CRC-16 of entire atom (type, count, dlen, data)
 /// ```
 /// The dlen and crc16 are not stored in this struct as they are generated on demand.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EepAtom {
     /// The Atom Type as defined by [`EepAtomType`]
     atype: EepAtomType,
@@ -331,6 +1811,29 @@ pub struct EepAtom {
 const ATOM_CRC16: Crc<u16> = Crc::<u16>::new(&CRC_16_ARC);
 
 impl EepAtom {
+    /// Return the Atom Type
+    pub fn atype(&self) -> EepAtomType {
+        self.atype
+    }
+
+    /// Return the value of the type field, which is also defined for Atoms of an unknown type
+    pub fn raw_atype(&self) -> u16 {
+        match &self.data {
+            EepAtomData::Unknown { atype, .. } => *atype,
+            _ => self.atype as u16,
+        }
+    }
+
+    /// Return the atom count
+    pub fn count(&self) -> u16 {
+        self.count
+    }
+
+    /// Return the Atom data
+    pub fn data(&self) -> &EepAtomData {
+        &self.data
+    }
+
     pub fn new_vendor_info(data: EepAtomVendorData) -> EepAtom {
         EepAtom {
             atype: EepAtomType::VendorInfo,
@@ -370,6 +1873,20 @@ impl EepAtom {
             data: EepAtomData::GpioBank1Map(data),
         }
     }
+
+    /// Create an opaque Atom of a reserved type (0x0006-0xfffe)
+    ///
+    /// The data is written unchanged. Known and invalid types are rejected.
+    pub fn new_unknown(atype: u16, raw: Vec<u8>) -> Result<EepAtom, EepError> {
+        if !(0x0006..=0xfffe).contains(&atype) {
+            return Err(EepError::NotReservedAtomType(atype));
+        }
+        Ok(EepAtom {
+            atype: EepAtomType::Unknown,
+            count: 0xffff,
+            data: EepAtomData::Unknown { atype, raw },
+        })
+    }
 }
 
 impl ToBytes for EepAtom {
@@ -385,11 +1902,16 @@ impl ToBytes for EepAtom {
         2 + 2 + 4 + self.data.len() + 2
     }
 
+    /// # Panics
+    ///
+    /// Panics if the data doesn't fit into the dlen field (see [`Eep::validate_sizes()`]).
     fn to_bytes(&self, buf: &mut Vec<u8>) {
-        let atype = self.atype as u16;
-        buf.extend_from_slice(&atype.to_le_bytes());
+        buf.extend_from_slice(&self.raw_atype().to_le_bytes());
         buf.extend_from_slice(&self.count.to_le_bytes());
-        let dlen = self.data.len() as u32 + 2;
+        let dlen = match atom_dlen(self.count, self.data.len()) {
+            Ok(dlen) => dlen,
+            Err(e) => panic!("{e}"),
+        };
         buf.extend_from_slice(&dlen.to_le_bytes());
         self.data.to_bytes(buf);
 
@@ -399,6 +1921,56 @@ impl ToBytes for EepAtom {
     }
 }
 
+/// Return the dlen field of the Atom `count` with `len` bytes of data
+///
+/// The dlen includes the CRC16, so the data is limited to `u32::MAX - 2` bytes.
+fn atom_dlen(count: u16, len: usize) -> Result<u32, EepError> {
+    len.checked_add(2)
+        .and_then(|dlen| u32::try_from(dlen).ok())
+        .ok_or(EepError::AtomTooLarge { count, len })
+}
+
+#[test]
+fn test_atom_dlen() {
+    assert_eq!(atom_dlen(0, 0), Ok(2));
+    assert_eq!(atom_dlen(0, u32::MAX as usize - 2), Ok(u32::MAX));
+    assert_eq!(
+        atom_dlen(3, u32::MAX as usize - 1),
+        Err(EepError::AtomTooLarge {
+            count: 3,
+            len: u32::MAX as usize - 1
+        })
+    );
+    assert!(atom_dlen(3, usize::MAX).is_err());
+    assert_eq!(
+        atom_dlen(3, u32::MAX as usize).unwrap_err().to_string(),
+        format!(
+            "The data of Atom 3 is too large: {} bytes (max: 4294967293 bytes)",
+            u32::MAX
+        )
+    );
+    assert_eq!(test_eep().validate_sizes(), Ok(()));
+    // Eep::push() reports the same error
+    let e = EepError::AtomTooLarge {
+        count: 3,
+        len: u32::MAX as usize,
+    };
+    assert_eq!(EepPushError::from(e.clone()).to_string(), e.to_string());
+}
+
+/// The byte order used to store the UUID in the Vendor info Atom
+///
+/// The HAT specification stores the UUID in reversed byte order. The normal (RFC 4122) byte order
+/// is only meant to create test images for readers that might expect it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UuidByteOrder {
+    /// The bytes of the UUID are stored in reversed order (HAT specification)
+    #[default]
+    Reversed,
+    /// The bytes of the UUID are stored in the same order as defined by RFC 4122
+    Normal,
+}
+
 /// This struct implements the Vendor info Atom
 ///
 /// [Vendor info atom data](https://github.com/raspberrypi/hats/blob/9616b5cd2bdf3e1d2d0330611387d639c1916100/eeprom-format.md#vendor-info-atom-data-type0x0001):
@@ -413,7 +1985,7 @@ impl ToBytes for EepAtom {
 /// Y       pstr        ASCII product string e.g. "Special Sensor Board"
 /// ```
 /// The vslen and the pslen are implicitly given by the [`String`] type.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EepAtomVendorData {
     /// UUID (unique for every single board ever made)
     uuid: uuid::Uuid,
@@ -425,6 +1997,8 @@ pub struct EepAtomVendorData {
     vstr: String,
     /// ASCII product string e.g. "Special Sensor Board"
     pstr: String,
+    /// The byte order used to write the UUID
+    uuid_byte_order: UuidByteOrder,
 }
 
 impl EepAtomVendorData {
@@ -436,18 +2010,10 @@ impl EepAtomVendorData {
         pstr: String,
     ) -> Result<EepAtomVendorData, EepError> {
         if vstr.len() > u8::MAX as usize {
-            return Err(EepError(format!(
-                "Vendor string to long: {} (max: {} bytes)",
-                vstr.len(),
-                u8::MAX
-            )));
+            return Err(EepError::VendorStringTooLong(vstr.len()));
         }
         if pstr.len() > u8::MAX as usize {
-            return Err(EepError(format!(
-                "Product string to long: {} (max: {} bytes)",
-                pstr.len(),
-                u8::MAX
-            )));
+            return Err(EepError::ProductStringTooLong(pstr.len()));
         }
         Ok(EepAtomVendorData {
             uuid,
@@ -455,8 +2021,81 @@ impl EepAtomVendorData {
             pver,
             vstr,
             pstr,
+            uuid_byte_order: UuidByteOrder::default(),
         })
     }
+
+    /// Decode the data of a vendor info Atom
+    ///
+    /// The UUID is expected in `uuid_byte_order`, which is kept to write it back the same way.
+    fn from_bytes(
+        data: &[u8],
+        uuid_byte_order: UuidByteOrder,
+    ) -> Result<EepAtomVendorData, String> {
+        if data.len() < 22 {
+            return Err(format!(
+                "vendor info too short: {} (min: 22 bytes)",
+                data.len()
+            ));
+        }
+        let mut uuid: [u8; 16] = data[0..16].try_into().unwrap();
+        if uuid_byte_order == UuidByteOrder::Reversed {
+            uuid.reverse();
+        }
+        let pid = u16::from_le_bytes([data[16], data[17]]);
+        let pver = u16::from_le_bytes([data[18], data[19]]);
+        let vslen = data[20] as usize;
+        let pslen = data[21] as usize;
+        if data.len() != 22 + vslen + pslen {
+            return Err(format!(
+                "invalid length of vendor info: {} (expected: {} bytes)",
+                data.len(),
+                22 + vslen + pslen
+            ));
+        }
+        let vstr = String::from_utf8(data[22..22 + vslen].to_vec())
+            .map_err(|e| format!("invalid vendor string: {e}"))?;
+        let pstr = String::from_utf8(data[22 + vslen..].to_vec())
+            .map_err(|e| format!("invalid product string: {e}"))?;
+        let mut vendor =
+            EepAtomVendorData::new(uuid::Uuid::from_bytes(uuid), pid, pver, vstr, pstr)
+                .map_err(|e| e.to_string())?;
+        vendor.set_uuid_byte_order(uuid_byte_order);
+        Ok(vendor)
+    }
+
+    /// Return the UUID
+    pub fn uuid(&self) -> uuid::Uuid {
+        self.uuid
+    }
+
+    /// Return the product ID
+    pub fn pid(&self) -> u16 {
+        self.pid
+    }
+
+    /// Return the product version
+    pub fn pver(&self) -> u16 {
+        self.pver
+    }
+
+    /// Return the vendor string
+    pub fn vstr(&self) -> &str {
+        &self.vstr
+    }
+
+    /// Return the product string
+    pub fn pstr(&self) -> &str {
+        &self.pstr
+    }
+
+    /// Set the byte order used to write the UUID
+    ///
+    /// The default is [`UuidByteOrder::Reversed`] as defined by the HAT specification. Only use
+    /// [`UuidByteOrder::Normal`] to create images for interoperability tests.
+    pub fn set_uuid_byte_order(&mut self, order: UuidByteOrder) {
+        self.uuid_byte_order = order;
+    }
 }
 
 impl ToBytes for EepAtomVendorData {
@@ -475,9 +2114,14 @@ impl ToBytes for EepAtomVendorData {
     }
 
     fn to_bytes(&self, buf: &mut Vec<u8>) {
-        // The UUID is stored in reverse order in the EEPROM
-        for b in self.uuid.as_bytes().iter().rev() {
-            buf.push(*b)
+        match self.uuid_byte_order {
+            // The UUID is stored in reverse order in the EEPROM
+            UuidByteOrder::Reversed => {
+                for b in self.uuid.as_bytes().iter().rev() {
+                    buf.push(*b)
+                }
+            }
+            UuidByteOrder::Normal => buf.extend_from_slice(self.uuid.as_bytes()),
         }
         buf.extend_from_slice(&self.pid.to_le_bytes());
         buf.extend_from_slice(&self.pver.to_le_bytes());
@@ -503,6 +2147,15 @@ fn test_eep_atom_vendor_data() {
     let mut buf: Vec<u8> = Vec::new();
     data.to_bytes(&mut buf);
     assert_eq!(data.len(), buf.len());
+    assert_eq!(buf[0], 0xc8);
+    assert_eq!(buf[15], 0x67);
+
+    let mut data = data;
+    data.set_uuid_byte_order(UuidByteOrder::Normal);
+    let mut buf: Vec<u8> = Vec::new();
+    data.to_bytes(&mut buf);
+    assert_eq!(data.len(), buf.len());
+    assert_eq!(&buf[..16], uuid.as_bytes());
 
     let long_string: String = vec!['a'; 256].into_iter().collect();
     let data = EepAtomVendorData::new(
@@ -524,13 +2177,74 @@ fn test_eep_atom_vendor_data() {
     assert!(data.is_err());
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum LinuxDTB {
     Blob(Vec<u8>),
     Name(String),
 }
 
-#[derive(Debug)]
+/// The magic number at the start of a (big endian) flattened device tree blob
+const FDT_MAGIC: [u8; 4] = [0xd0, 0x0d, 0xfe, 0xed];
+
+impl LinuxDTB {
+    /// Decode the data of a Linux device tree blob Atom
+    ///
+    /// Both variants are written as plain bytes, so the variant is guessed: data starting with the
+    /// device tree magic `0xd00dfeed` is a [`LinuxDTB::Blob`]. Otherwise non-empty data consisting
+    /// only of `A-Z`, `a-z`, `0-9`, `.`, `_`, `-` and `,` is the [`LinuxDTB::Name`] of an overlay.
+    /// Any other data is returned as [`LinuxDTB::Blob`].
+    pub fn from_bytes(data: &[u8]) -> LinuxDTB {
+        if !data.starts_with(&FDT_MAGIC)
+            && !data.is_empty()
+            && data
+                .iter()
+                .all(|c| c.is_ascii_alphanumeric() || b"._-,".contains(c))
+        {
+            // only ASCII characters, so it is valid UTF-8
+            LinuxDTB::Name(String::from_utf8_lossy(data).into_owned())
+        } else {
+            LinuxDTB::Blob(data.to_vec())
+        }
+    }
+
+    /// Return the bytes written to the Atom
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            LinuxDTB::Blob(data) => data,
+            LinuxDTB::Name(data) => data.as_bytes(),
+        }
+    }
+}
+
+#[test]
+fn test_linux_dtb_from_bytes() {
+    assert_eq!(
+        LinuxDTB::from_bytes(b"revpi-core-2022"),
+        LinuxDTB::Name("revpi-core-2022".to_string())
+    );
+    assert_eq!(
+        LinuxDTB::from_bytes(b"vendor,overlay_1.2"),
+        LinuxDTB::Name("vendor,overlay_1.2".to_string())
+    );
+    let blob = [0xd0, 0x0d, 0xfe, 0xed, 0x00, 0x00, 0x01, 0x00];
+    assert_eq!(LinuxDTB::from_bytes(&blob), LinuxDTB::Blob(blob.to_vec()));
+    // ambiguous data
+    for data in [
+        &b""[..],
+        b"name\0",
+        b"two words",
+        "\u{e4}".as_bytes(),
+        &[0xff, 0xfe],
+    ] {
+        assert_eq!(LinuxDTB::from_bytes(data), LinuxDTB::Blob(data.to_vec()));
+    }
+}
+
+/// The data of a Linux device tree blob Atom
+///
+/// Two instances are equal if they are written to the same bytes, e.g. the [`LinuxDTB::Name`] of an
+/// image created from a configuration and the [`LinuxDTB::Blob`] decoded from it.
+#[derive(Clone, Debug, Eq)]
 pub struct EepAtomLinuxDTBData {
     data: LinuxDTB,
 }
@@ -539,6 +2253,17 @@ impl EepAtomLinuxDTBData {
     pub fn new(data: LinuxDTB) -> EepAtomLinuxDTBData {
         EepAtomLinuxDTBData { data }
     }
+
+    /// Return the device tree blob or the name of the device tree overlay
+    pub fn data(&self) -> &LinuxDTB {
+        &self.data
+    }
+}
+
+impl PartialEq for EepAtomLinuxDTBData {
+    fn eq(&self, other: &Self) -> bool {
+        self.data.as_bytes() == other.data.as_bytes()
+    }
 }
 
 impl ToBytes for EepAtomLinuxDTBData {
@@ -557,7 +2282,7 @@ impl ToBytes for EepAtomLinuxDTBData {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EepAtomCustomData {
     data: Vec<u8>,
 }
@@ -566,6 +2291,11 @@ impl EepAtomCustomData {
     pub fn new(data: Vec<u8>) -> EepAtomCustomData {
         EepAtomCustomData { data }
     }
+
+    /// Return the custom data
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
 }
 
 impl ToBytes for EepAtomCustomData {